@@ -0,0 +1,124 @@
+//! Compares `Grid`'s flat storage against the `Vec<Vec<T>>` it used to be backed by
+//!
+//! run with `cargo bench -p shared --features types_2d`
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use shared::{
+    testing::Lcg,
+    types_2d::{Coords, Grid},
+};
+
+/// the nested storage `Grid` used before it was flattened
+struct NestedGrid<T> {
+    cells: Vec<Vec<T>>,
+}
+
+impl<T> NestedGrid<T> {
+    fn get(&self, index: Coords) -> Option<&T> {
+        self.cells.get(index.y).and_then(|row| row.get(index.x))
+    }
+}
+
+fn bench(name: &str, mut f: impl FnMut() -> u64) {
+    const ITERATIONS: u32 = 20;
+    let mut total = Duration::ZERO;
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        black_box(f());
+        total += start.elapsed();
+    }
+    println!("{name:<32} {:>12.3?} per iteration", total / ITERATIONS);
+}
+
+fn main() {
+    const SIZE: usize = 2000;
+    let mut rng = Lcg::new(41);
+    let rows: Vec<Vec<u32>> = (0..SIZE)
+        .map(|_| (0..SIZE).map(|_| rng.below(10) as u32).collect())
+        .collect();
+    let nested = NestedGrid {
+        cells: rows.clone(),
+    };
+    let flat = Grid::from_rows(rows).expect("rectangular rows");
+
+    bench("scan: nested", || {
+        (0..SIZE)
+            .flat_map(|y| (0..SIZE).map(move |x| Coords { x, y }))
+            .map(|coords| *nested.get(coords).unwrap() as u64)
+            .sum()
+    });
+    bench("scan: flat", || {
+        (0..SIZE)
+            .flat_map(|y| (0..SIZE).map(move |x| Coords { x, y }))
+            .map(|coords| *flat.get(coords).unwrap() as u64)
+            .sum()
+    });
+
+    // down the columns, which jumps between rows on every step
+    bench("columns: nested", || {
+        (0..SIZE)
+            .flat_map(|x| (0..SIZE).map(move |y| Coords { x, y }))
+            .map(|coords| *nested.get(coords).unwrap() as u64)
+            .sum()
+    });
+    bench("columns: flat", || {
+        (0..SIZE)
+            .flat_map(|x| flat.column(x).unwrap())
+            .map(|&height| height as u64)
+            .sum()
+    });
+
+    // the four neighbours of every cell, like a path search looking at each step
+    bench("neighbours: nested", || {
+        (1..SIZE - 1)
+            .flat_map(|y| (1..SIZE - 1).map(move |x| Coords { x, y }))
+            .map(|Coords { x, y }| {
+                [(x, y - 1), (x + 1, y), (x, y + 1), (x - 1, y)]
+                    .into_iter()
+                    .map(|(x, y)| *nested.get(Coords { x, y }).unwrap() as u64)
+                    .sum::<u64>()
+            })
+            .sum()
+    });
+    bench("neighbours: flat", || {
+        (1..SIZE - 1)
+            .flat_map(|y| (1..SIZE - 1).map(move |x| Coords { x, y }))
+            .map(|Coords { x, y }| {
+                [(x, y - 1), (x + 1, y), (x, y + 1), (x - 1, y)]
+                    .into_iter()
+                    .map(|(x, y)| *flat.get(Coords { x, y }).unwrap() as u64)
+                    .sum::<u64>()
+            })
+            .sum()
+    });
+
+    bench("clone: nested", || {
+        black_box(nested.cells.clone()).len() as u64
+    });
+    bench("clone: flat", || {
+        black_box(flat.clone()).cells().len() as u64
+    });
+
+    let lookups: Vec<Coords> = (0..1_000_000)
+        .map(|_| Coords {
+            x: rng.below(SIZE as u64) as usize,
+            y: rng.below(SIZE as u64) as usize,
+        })
+        .collect();
+    bench("random: nested", || {
+        lookups
+            .iter()
+            .map(|&coords| *nested.get(coords).unwrap() as u64)
+            .sum()
+    });
+    bench("random: flat", || {
+        lookups
+            .iter()
+            .map(|&coords| *flat.get(coords).unwrap() as u64)
+            .sum()
+    });
+}
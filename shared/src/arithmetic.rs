@@ -1,12 +1,25 @@
+use std::{
+    fmt,
+    ops::{Add, Div, Mul, Neg, Rem, Shl, Shr, Sub},
+};
+
+/// greatest common divisor of any two integers of the same type
+///
+/// The result is always non-negative, so it is returned as the unsigned type of the same width:
+/// `gcd(i64::MIN, 0)` is 2⁶³ which doesn't fit in an `i64`.
+pub fn gcd<T: Gcd>(u: T, v: T) -> T::Unsigned {
+    binary_gcd(u.unsigned_abs(), v.unsigned_abs())
+}
+
 /// shamelessly stolen from [wikipedia](https://en.wikipedia.org/wiki/Binary_GCD_algorithm#Implementation) of all places
-pub fn gcd(mut u: usize, mut v: usize) -> usize {
+fn binary_gcd<U: Gcd>(mut u: U, mut v: U) -> U {
     use std::cmp::min;
     use std::mem::swap;
 
     // Base cases: gcd(n, 0) = gcd(0, n) = n
-    if u == 0 {
+    if u == U::ZERO {
         return v;
-    } else if v == 0 {
+    } else if v == U::ZERO {
         return u;
     }
 
@@ -14,15 +27,15 @@ pub fn gcd(mut u: usize, mut v: usize) -> usize {
     // gcd(2ⁱ u, 2ʲ v) = 2ᵏ gcd(u, v) with u, v odd and k = min(i, j)
     // 2ᵏ is the greatest power of two that divides both u and v
     let i = u.trailing_zeros();
-    u >>= i;
+    u = u >> i;
     let j = v.trailing_zeros();
-    v >>= j;
+    v = v >> j;
     let k = min(i, j);
 
     loop {
         // u and v are odd at the start of the loop
-        debug_assert!(u % 2 == 1, "u = {} is even", u);
-        debug_assert!(v % 2 == 1, "v = {} is even", v);
+        debug_assert!(u % U::TWO == U::ONE, "u = {} is even", u);
+        debug_assert!(v % U::TWO == U::ONE, "v = {} is even", v);
 
         // Swap if necessary so u <= v
         if u > v {
@@ -31,17 +44,598 @@ pub fn gcd(mut u: usize, mut v: usize) -> usize {
         // u and v are still both odd after (potentially) swapping
 
         // Using identity 4 (gcd(u, v) = gcd(|v-u|, min(u, v))
-        v -= u;
+        v = v - u;
         // v is now even, but u is unchanged (and odd)
 
         // Identity 1: gcd(u, 0) = u
         // The shift by k is necessary to add back the 2ᵏ factor that was removed before the loop
-        if v == 0 {
+        if v == U::ZERO {
             return u << k;
         }
 
         // Identity 3: gcd(u, 2ʲ v) = gcd(u, v) (u is known to be odd)
-        v >>= v.trailing_zeros();
+        v = v >> v.trailing_zeros();
         // v is now odd again
     }
 }
+
+/// lowest common multiple, built on [`gcd`]
+///
+/// panics on overflow in debug builds, see [`checked_lcm`] for a non-panicking version
+pub fn lcm<T: Gcd>(u: T, v: T) -> T::Unsigned {
+    let (u, v) = (u.unsigned_abs(), v.unsigned_abs());
+    if u == T::Unsigned::ZERO || v == T::Unsigned::ZERO {
+        return T::Unsigned::ZERO;
+    }
+    // divide first to keep the intermediate value as small as possible
+    u / binary_gcd(u, v) * v
+}
+
+/// lowest common multiple, returning `None` if the result would overflow
+pub fn checked_lcm<T: Gcd>(u: T, v: T) -> Option<T::Unsigned> {
+    let (u, v) = (u.unsigned_abs(), v.unsigned_abs());
+    if u == T::Unsigned::ZERO || v == T::Unsigned::ZERO {
+        return Some(T::Unsigned::ZERO);
+    }
+    (u / binary_gcd(u, v)).checked_mul(v)
+}
+
+/// Integers that have a [`gcd`] and [`lcm`]
+pub trait Gcd: Integer + Shl<u32, Output = Self> + Shr<u32, Output = Self> {
+    /// the unsigned type of the same width, which can hold the absolute value of any `Self`
+    type Unsigned: Gcd<Unsigned = Self::Unsigned>;
+
+    fn unsigned_abs(self) -> Self::Unsigned;
+    fn trailing_zeros(self) -> u32;
+}
+
+macro_rules! impl_gcd {
+    ($($t:ty => $u:ty, $abs:expr);* $(;)?) => {
+        $(
+            impl Gcd for $t {
+                type Unsigned = $u;
+
+                fn unsigned_abs(self) -> Self::Unsigned {
+                    let abs: fn($t) -> $u = $abs;
+                    abs(self)
+                }
+
+                fn trailing_zeros(self) -> u32 {
+                    <$t>::trailing_zeros(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_gcd!(
+    i8 => u8, i8::unsigned_abs;
+    i16 => u16, i16::unsigned_abs;
+    i32 => u32, i32::unsigned_abs;
+    i64 => u64, i64::unsigned_abs;
+    i128 => u128, i128::unsigned_abs;
+    isize => usize, isize::unsigned_abs;
+    u8 => u8, |n| n;
+    u16 => u16, |n| n;
+    u32 => u32, |n| n;
+    u64 => u64, |n| n;
+    u128 => u128, |n| n;
+    usize => usize, |n| n;
+);
+
+/// The primitive integer types, so that number theory functions can be written once for all of them
+///
+/// Functions built on this trait use checked arithmetic (or avoid overflow entirely) rather than silently wrapping.
+pub trait Integer:
+    Copy
+    + Ord
+    + fmt::Debug
+    + fmt::Display
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Rem<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+    const TWO: Self;
+    const MIN: Self;
+    const MAX: Self;
+
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+    fn checked_sub(self, rhs: Self) -> Option<Self>;
+    fn checked_mul(self, rhs: Self) -> Option<Self>;
+    /// `None` for the `MIN` value of signed types, which has no positive counterpart
+    fn checked_abs(self) -> Option<Self>;
+    /// the least non-negative remainder of `self / rhs`
+    fn rem_euclid(self, rhs: Self) -> Self;
+
+    fn is_negative(self) -> bool {
+        self < Self::ZERO
+    }
+}
+
+macro_rules! impl_integer {
+    ($($t:ty),* ; abs: $abs:expr) => {
+        $(
+            impl Integer for $t {
+                const ZERO: Self = 0;
+                const ONE: Self = 1;
+                const TWO: Self = 2;
+                const MIN: Self = <$t>::MIN;
+                const MAX: Self = <$t>::MAX;
+
+                fn checked_add(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_add(self, rhs)
+                }
+
+                fn checked_sub(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_sub(self, rhs)
+                }
+
+                fn checked_mul(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_mul(self, rhs)
+                }
+
+                fn checked_abs(self) -> Option<Self> {
+                    let abs: fn($t) -> Option<$t> = $abs;
+                    abs(self)
+                }
+
+                fn rem_euclid(self, rhs: Self) -> Self {
+                    <$t>::rem_euclid(self, rhs)
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(i8, i16, i32, i64, i128, isize; abs: |n| n.checked_abs());
+impl_integer!(u8, u16, u32, u64, u128, usize; abs: Some);
+
+/// euclid's algorithm, for non-negative `u` and `v`
+fn euclid<T: Integer>(mut u: T, mut v: T) -> T {
+    while v != T::ZERO {
+        (u, v) = (v, u % v);
+    }
+    u
+}
+
+/// `(a + b) % m` for `a` and `b` already reduced modulo `m`, without overflowing
+fn add_mod<T: Integer>(a: T, b: T, m: T) -> T {
+    let headroom = m - b;
+    if a >= headroom {
+        a - headroom
+    } else {
+        a + b
+    }
+}
+
+/// `(a - b) % m` for `a` and `b` already reduced modulo `m`, without overflowing
+fn sub_mod<T: Integer>(a: T, b: T, m: T) -> T {
+    if a >= b {
+        a - b
+    } else {
+        a + (m - b)
+    }
+}
+
+/// `(a * b) % m` for any `a` and `b` and a positive `m`, without overflowing
+pub fn mul_mod<T: Integer>(a: T, b: T, m: T) -> T {
+    assert!(m > T::ZERO, "modulus must be positive");
+    let a = a.rem_euclid(m);
+    let mut b = b.rem_euclid(m);
+    if let Some(product) = a.checked_mul(b) {
+        return product % m;
+    }
+
+    // fall back to double-and-add, which only ever needs numbers less than 2m
+    let mut result = T::ZERO;
+    let mut doubling = a;
+    while b > T::ZERO {
+        if b % T::TWO == T::ONE {
+            result = add_mod(result, doubling, m);
+        }
+        doubling = add_mod(doubling, doubling, m);
+        b = b / T::TWO;
+    }
+    result
+}
+
+/// `base^exp % m` by repeated squaring, without overflowing
+///
+/// panics if `m` is not positive or `exp` is negative
+pub fn mod_pow<T: Integer>(base: T, mut exp: T, m: T) -> T {
+    assert!(m > T::ZERO, "modulus must be positive");
+    assert!(!exp.is_negative(), "exponent must not be negative");
+
+    let mut result = T::ONE % m;
+    let mut base = base.rem_euclid(m);
+    while exp > T::ZERO {
+        if exp % T::TWO == T::ONE {
+            result = mul_mod(result, base, m);
+        }
+        base = mul_mod(base, base, m);
+        exp = exp / T::TWO;
+    }
+    result
+}
+
+/// lowest common multiple of every value, `None` if the result would overflow
+///
+/// the result is always non-negative, the lcm of an empty slice is 1
+pub fn lcm_all<T: Integer>(values: &[T]) -> Option<T> {
+    values.iter().try_fold(T::ONE, |acc, &value| {
+        let value = value.checked_abs()?;
+        if acc == T::ZERO || value == T::ZERO {
+            return Some(T::ZERO);
+        }
+        (acc / euclid(acc, value)).checked_mul(value)
+    })
+}
+
+/// extended euclidean algorithm: find `(g, x, y)` such that `a*x + b*y == g == gcd(a, b)`
+///
+/// `g` is always non-negative. Returns `None` if any intermediate value would overflow,
+/// which only happens for inputs near the type's `MIN`
+pub fn extended_gcd<T>(a: T, b: T) -> Option<(T, T, T)>
+where
+    T: Integer + Neg<Output = T>,
+{
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (T::ONE, T::ZERO);
+    let (mut old_y, mut y) = (T::ZERO, T::ONE);
+
+    while r != T::ZERO {
+        let quotient = old_r / r;
+        (old_r, r) = (r, old_r.checked_sub(quotient.checked_mul(r)?)?);
+        (old_x, x) = (x, old_x.checked_sub(quotient.checked_mul(x)?)?);
+        (old_y, y) = (y, old_y.checked_sub(quotient.checked_mul(y)?)?);
+    }
+
+    if old_r.is_negative() {
+        Some((old_r.checked_abs()?, -old_x, -old_y))
+    } else {
+        Some((old_r, old_x, old_y))
+    }
+}
+
+/// the modular inverse of `a` modulo `m`: `x` such that `(a * x) % m == 1`, in the range `0..m`
+///
+/// Returns `None` if `a` and `m` are not coprime (or `m` is not positive), because there is no inverse.
+/// Works for unsigned types too, by keeping the Bézout coefficient reduced modulo `m` throughout.
+pub fn mod_inverse<T: Integer>(a: T, m: T) -> Option<T> {
+    if m <= T::ZERO {
+        return None;
+    }
+    let (mut old_r, mut r) = (a.rem_euclid(m), m);
+    let (mut old_x, mut x) = (T::ONE % m, T::ZERO);
+
+    while r != T::ZERO {
+        let quotient = old_r / r;
+        (old_r, r) = (r, old_r - quotient * r);
+        (old_x, x) = (x, sub_mod(old_x, mul_mod(quotient, x, m), m));
+    }
+
+    if old_r == T::ONE {
+        Some(old_x)
+    } else {
+        None
+    }
+}
+
+/// chinese remainder theorem: find the smallest non-negative `x` such that `x % modulus == residue` for each
+/// `(residue, modulus)` pair, along with the lcm of the moduli
+///
+/// Moduli don't need to be coprime. Returns `None` if there is no solution, if any modulus is not positive,
+/// or if the lcm of the moduli would overflow.
+pub fn crt<T: Integer>(congruences: &[(T, T)]) -> Option<(T, T)> {
+    congruences
+        .iter()
+        .try_fold((T::ZERO, T::ONE), |(x, lcm), &(residue, modulus)| {
+            if modulus <= T::ZERO {
+                return None;
+            }
+            let residue = residue.rem_euclid(modulus);
+
+            // x + lcm * t == residue (mod modulus), solve for t
+            let g = euclid(lcm, modulus);
+            let difference = sub_mod(residue, x % modulus, modulus);
+            if difference % g != T::ZERO {
+                return None;
+            }
+            let reduced_modulus = modulus / g;
+            let inverse = mod_inverse(lcm / g, reduced_modulus)?;
+            let t = mul_mod(difference / g, inverse, reduced_modulus);
+
+            let new_lcm = (lcm / g).checked_mul(modulus)?;
+            // t < modulus / g so lcm * t < new_lcm, which we know fits
+            Some((add_mod(x, lcm * t, new_lcm), new_lcm))
+        })
+}
+
+/// the largest integer whose square is no more than `n`
+///
+/// panics if `n` is negative
+pub fn isqrt<T: Integer>(n: T) -> T {
+    assert!(
+        !n.is_negative(),
+        "cannot take the square root of a negative number"
+    );
+    if n < T::TWO {
+        return n;
+    }
+
+    // newton's method, descending from above until it stops getting smaller
+    let mut x = n;
+    loop {
+        let quotient = n / x;
+        // (x + quotient) / 2 without risking x + quotient overflowing
+        let y = x / T::TWO + quotient / T::TWO + (x % T::TWO + quotient % T::TWO) / T::TWO;
+        if y >= x {
+            return x;
+        }
+        x = y;
+    }
+}
+
+/// the prime factors of `n` in ascending order, paired with how many times each one divides `n`
+///
+/// panics if `n` is not positive
+pub fn prime_factors<T: Integer>(mut n: T) -> Vec<(T, u32)> {
+    assert!(
+        n > T::ZERO,
+        "only positive numbers have a prime factorisation"
+    );
+    let mut factors = Vec::new();
+
+    let mut divisor = T::TWO;
+    // divisor <= n / divisor rather than divisor * divisor <= n to avoid overflow
+    while divisor <= n / divisor {
+        let mut count = 0;
+        while n % divisor == T::ZERO {
+            n = n / divisor;
+            count += 1;
+        }
+        if count > 0 {
+            factors.push((divisor, count));
+        }
+        divisor = divisor + T::ONE;
+    }
+    if n > T::ONE {
+        factors.push((n, 1));
+    }
+    factors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Lcg;
+
+    #[test]
+    fn test_lcm() {
+        assert_eq!(lcm(4usize, 6), 12);
+        assert_eq!(lcm(6usize, 4), 12);
+        assert_eq!(lcm(7usize, 13), 91);
+        assert_eq!(lcm(5usize, 0), 0);
+        assert_eq!(lcm(12usize, 12), 12);
+    }
+
+    #[test]
+    fn test_checked_lcm_overflow() {
+        assert_eq!(checked_lcm(usize::MAX, 2), None);
+        assert_eq!(checked_lcm(usize::MAX, usize::MAX), Some(usize::MAX));
+        assert_eq!(checked_lcm(23usize * 19, 13 * 17), Some(23 * 19 * 13 * 17));
+        assert_eq!(checked_lcm(i64::MIN, 2), Some(1 << 63));
+        assert_eq!(checked_lcm(i64::MIN, 3), None);
+    }
+
+    #[test]
+    fn test_gcd_signed() {
+        assert_eq!(gcd(-4i32, 6), 2u32);
+        assert_eq!(gcd(4i32, -6), 2);
+        assert_eq!(gcd(-4i32, -6), 2);
+        assert_eq!(gcd(-7i64, 0), 7u64);
+        assert_eq!(gcd(0i64, 0), 0);
+        assert_eq!(lcm(-4i32, 6), 12u32);
+        assert_eq!(lcm(-4i32, -6), 12);
+    }
+
+    #[test]
+    fn test_gcd_min() {
+        assert_eq!(gcd(i64::MIN, 0), 1 << 63);
+        assert_eq!(gcd(i64::MIN, i64::MIN), 1 << 63);
+        assert_eq!(gcd(i64::MIN, 6), 2);
+        assert_eq!(gcd(i64::MIN, i64::MAX), 1);
+        assert_eq!(gcd(i8::MIN, -64), 64u8);
+        assert_eq!(gcd(i128::MIN, 3 << 100), 1 << 100);
+        assert_eq!(gcd(u128::MAX, u128::MAX), u128::MAX);
+    }
+
+    #[test]
+    fn test_gcd_matches_naive() {
+        for u in -50i32..50 {
+            for v in -50i32..50 {
+                assert_eq!(
+                    gcd(u, v) as i128,
+                    naive_gcd(u as i128, v as i128),
+                    "gcd({u}, {v})"
+                );
+                assert_eq!(
+                    gcd(u as i8, v as i8) as i128,
+                    naive_gcd(u as i128, v as i128)
+                );
+            }
+        }
+    }
+
+    fn naive_gcd(u: i128, v: i128) -> i128 {
+        let (u, v) = (u.abs(), v.abs());
+        (1..=u.max(v))
+            .rev()
+            .find(|d| u % d == 0 && v % d == 0)
+            .unwrap_or(0)
+    }
+
+    fn naive_mod_pow(base: u128, exp: u128, m: u128) -> u128 {
+        (0..exp).fold(1 % m, |acc, _| acc * base % m)
+    }
+
+    #[test]
+    fn test_mul_mod_does_not_overflow() {
+        assert_eq!(mul_mod(u64::MAX - 1, u64::MAX - 1, u64::MAX), 1);
+        assert_eq!(mul_mod(u128::MAX - 1, 2, u128::MAX), u128::MAX - 2);
+        assert_eq!(mul_mod(i64::MIN, i64::MIN, i64::MAX), 1);
+        assert_eq!(mul_mod(-3i64, 5, 7), 6);
+    }
+
+    #[test]
+    fn test_mod_pow() {
+        let mut rng = Lcg::new(1);
+        for _ in 0..500 {
+            let base = rng.below(1000) as u128;
+            let exp = rng.below(50) as u128;
+            let m = rng.below(1000) as u128 + 1;
+            assert_eq!(mod_pow(base, exp, m), naive_mod_pow(base, exp, m));
+            assert_eq!(
+                mod_pow(base as u64, exp as u64, m as u64) as u128,
+                naive_mod_pow(base, exp, m)
+            );
+            assert_eq!(
+                mod_pow(-(base as i64), exp as i64, m as i64) as u128,
+                naive_mod_pow((m - base % m) % m, exp, m)
+            );
+        }
+        // fermat's little theorem with a large prime
+        let p: u64 = 18446744073709551557;
+        assert_eq!(mod_pow(123456789, p - 1, p), 1);
+        assert_eq!(mod_pow(2u128, 127, u128::MAX), 1 << 127);
+    }
+
+    #[test]
+    fn test_lcm_all() {
+        let mut rng = Lcg::new(2);
+        for _ in 0..500 {
+            let values: Vec<i64> = (0..3).map(|_| rng.below(200) as i64 - 100).collect();
+            let expected = if values.contains(&0) {
+                0
+            } else {
+                (1..).find(|n| values.iter().all(|v| n % v == 0)).unwrap()
+            };
+            assert_eq!(lcm_all(&values), Some(expected), "{values:?}");
+        }
+        assert_eq!(lcm_all::<u64>(&[]), Some(1));
+        assert_eq!(lcm_all(&[u64::MAX, 2]), None);
+        assert_eq!(lcm_all(&[i64::MIN]), None);
+        assert_eq!(lcm_all(&[23u128, 19, 13, 17]), Some(96577));
+    }
+
+    #[test]
+    fn test_extended_gcd() {
+        let mut rng = Lcg::new(3);
+        for _ in 0..500 {
+            let a = rng.below(2000) as i64 - 1000;
+            let b = rng.below(2000) as i64 - 1000;
+            let (g, x, y) = extended_gcd(a, b).unwrap();
+            assert_eq!(g as i128, naive_gcd(a as i128, b as i128), "gcd({a}, {b})");
+            assert_eq!(a * x + b * y, g, "bezout({a}, {b})");
+        }
+        assert_eq!(extended_gcd(i128::MAX, 2), Some((1, 1, -(i128::MAX / 2))));
+        assert_eq!(extended_gcd(i64::MIN, 0), None);
+    }
+
+    #[test]
+    fn test_mod_inverse() {
+        for m in 1u64..100 {
+            for a in 0..m {
+                let naive = (0..m).find(|x| (a * x) % m == 1 % m);
+                assert_eq!(mod_inverse(a, m), naive, "inverse of {a} mod {m}");
+                assert_eq!(
+                    mod_inverse(a as i64 - m as i64, m as i64),
+                    naive.map(|x| x as i64)
+                );
+            }
+        }
+        let p: u128 = 340282366920938463463374607431768211297; // the largest prime below 2^128
+        let inverse = mod_inverse(u128::MAX, p).unwrap();
+        assert_eq!(mul_mod(inverse, u128::MAX, p), 1);
+        assert_eq!(mod_inverse(5, 0), None);
+        assert_eq!(mod_inverse(5, -7), None);
+    }
+
+    #[test]
+    fn test_crt() {
+        let mut rng = Lcg::new(4);
+        for _ in 0..500 {
+            let congruences: Vec<(u64, u64)> = (0..3)
+                .map(|_| {
+                    let m = rng.below(20) + 1;
+                    (rng.below(100), m)
+                })
+                .collect();
+            let lcm = lcm_all(&congruences.iter().map(|(_, m)| *m).collect::<Vec<_>>()).unwrap();
+            let naive = (0..lcm).find(|x| congruences.iter().all(|(r, m)| x % m == r % m));
+            assert_eq!(
+                crt(&congruences),
+                naive.map(|x| (x, lcm)),
+                "{congruences:?}"
+            );
+        }
+        assert_eq!(crt(&[(2i64, 3), (3, 5), (2, 7)]), Some((23, 105)));
+        assert_eq!(crt(&[(-1i64, 3), (-1, 5)]), Some((14, 15)));
+        assert_eq!(crt(&[(0u64, 0)]), None);
+        assert_eq!(crt(&[(0u64, u64::MAX), (0, u64::MAX - 1)]), None);
+        // large coprime moduli, where intermediate products would overflow
+        let (a, b) = (1u128 << 64, 3u128.pow(40));
+        let (x, lcm) = crt(&[(1, a), (0, b)]).unwrap();
+        assert_eq!(lcm, a * b);
+        assert!(x < lcm);
+        assert_eq!(x % a, 1);
+        assert_eq!(x % b, 0);
+        assert_eq!(crt(&[(1, a), (0, b), (0, 2)]), None);
+        assert_eq!(crt(&[(1, a), (0, b), (0, 5)]), None);
+    }
+
+    #[test]
+    fn test_isqrt() {
+        for n in 0u64..10_000 {
+            let naive = (0..=n).take_while(|r| r * r <= n).last().unwrap();
+            assert_eq!(isqrt(n), naive, "isqrt({n})");
+            assert_eq!(isqrt(n as i64), naive as i64);
+        }
+        assert_eq!(isqrt(u64::MAX), u32::MAX as u64);
+        assert_eq!(isqrt(u128::MAX), u64::MAX as u128);
+        assert_eq!(isqrt(i128::MAX), 13043817825332782212);
+        assert_eq!(isqrt(i64::MAX), 3037000499);
+    }
+
+    #[test]
+    fn test_prime_factors() {
+        for n in 1u64..2_000 {
+            let factors = prime_factors(n);
+            assert_eq!(factors.iter().map(|(p, k)| p.pow(*k)).product::<u64>(), n);
+            for (p, _) in factors {
+                assert!((2..p).all(|d| p % d != 0), "{p} is not prime");
+            }
+        }
+        assert_eq!(prime_factors(360i64), vec![(2, 3), (3, 2), (5, 1)]);
+        assert_eq!(
+            prime_factors(u64::MAX),
+            vec![
+                (3, 1),
+                (5, 1),
+                (17, 1),
+                (257, 1),
+                (641, 1),
+                (65537, 1),
+                (6700417, 1)
+            ]
+        );
+        assert_eq!(
+            prime_factors(10_007u64 * 10_009 * 10_009),
+            vec![(10_007, 1), (10_009, 2)]
+        );
+    }
+}
@@ -0,0 +1,315 @@
+use std::{collections::HashMap, fmt, hash::Hash, iter::Sum, ops::Mul};
+
+/// A cycle found in a sequence of states x0, x1 = f(x0), x2 = f(x1), ...
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Cycle {
+    /// the step at which the first state of the cycle appears (μ)
+    pub start: usize,
+    /// the number of steps it takes to get back to the same state (λ)
+    pub length: usize,
+}
+
+impl Cycle {
+    /// the earliest step whose state is identical to the state at step `n`
+    pub fn equivalent_step(&self, n: usize) -> usize {
+        if n < self.start {
+            n
+        } else {
+            self.start + (n - self.start) % self.length
+        }
+    }
+
+    /// the number of complete cycles contained in the first `n` steps, and how many steps are left over after them
+    ///
+    /// steps before the cycle starts are not counted in either
+    pub fn repeats(&self, n: usize) -> (usize, usize) {
+        let n = n.saturating_sub(self.start);
+        (n / self.length, n % self.length)
+    }
+
+    /// sum some value produced by each of the first `n` steps
+    ///
+    /// `per_step[i]` is the value produced stepping from state i to state i+1,
+    /// it must cover at least the steps up to the end of the first cycle (`start + length`).
+    pub fn extrapolate_sum<T>(&self, per_step: &[T], n: usize) -> T
+    where
+        T: Copy + Sum<T> + Mul<Output = T> + TryFrom<usize>,
+        <T as TryFrom<usize>>::Error: fmt::Debug,
+    {
+        if n <= per_step.len() {
+            return per_step[..n].iter().copied().sum();
+        }
+        assert!(
+            per_step.len() >= self.start + self.length,
+            "per_step must cover the whole cycle"
+        );
+
+        let prefix: T = per_step[..self.start].iter().copied().sum();
+        let cycle = &per_step[self.start..self.start + self.length];
+        let (repeats, remainder) = self.repeats(n);
+        let repeats = T::try_from(repeats).expect("number of repeats should fit in T");
+
+        let whole: T = cycle.iter().copied().sum();
+        let rest: T = cycle[..remainder].iter().copied().sum();
+
+        [prefix, whole * repeats, rest].into_iter().sum()
+    }
+}
+
+/// Find a cycle using Floyd's "tortoise and hare" algorithm
+///
+/// Only needs to keep two states in memory, but calls `step` more often than [`brent`].
+/// Returns `None` if no cycle is found within `limit` steps.
+pub fn floyd<S, F>(initial: S, limit: usize, mut step: F) -> Option<Cycle>
+where
+    S: PartialEq + Clone,
+    F: FnMut(&S) -> S,
+{
+    // the hare moves twice as fast, they meet somewhere inside the cycle
+    let mut tortoise = step(&initial);
+    let mut hare = step(&tortoise);
+    let mut steps = 1;
+    while tortoise != hare {
+        if steps >= limit {
+            return None;
+        }
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        hare = step(&hare);
+        steps += 1;
+    }
+
+    // the distance from the start to the cycle is the same as the distance from the meeting point to the cycle
+    let mut start = 0;
+    tortoise = initial;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        start += 1;
+    }
+
+    // walk around the cycle once to measure it
+    let mut length = 1;
+    hare = step(&tortoise);
+    while tortoise != hare {
+        hare = step(&hare);
+        length += 1;
+    }
+
+    Some(Cycle { start, length })
+}
+
+/// Find a cycle using Brent's algorithm
+///
+/// Only needs to keep two states in memory and is usually faster than [`floyd`].
+/// Returns `None` if no cycle is found within `limit` steps.
+pub fn brent<S, F>(initial: S, limit: usize, mut step: F) -> Option<Cycle>
+where
+    S: PartialEq + Clone,
+    F: FnMut(&S) -> S,
+{
+    // search successive powers of two for the length of the cycle
+    let mut power = 1;
+    let mut length = 1;
+    let mut steps = 1;
+    let mut tortoise = initial.clone();
+    let mut hare = step(&initial);
+    while tortoise != hare {
+        if steps >= limit {
+            return None;
+        }
+        if power == length {
+            tortoise = hare.clone();
+            power *= 2;
+            length = 0;
+        }
+        hare = step(&hare);
+        length += 1;
+        steps += 1;
+    }
+
+    // start the hare one cycle length ahead, then they will meet at the start of the cycle
+    tortoise = initial.clone();
+    hare = initial;
+    for _ in 0..length {
+        hare = step(&hare);
+    }
+    let mut start = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        start += 1;
+    }
+
+    Some(Cycle { start, length })
+}
+
+/// Detects a cycle by remembering every state it is shown
+///
+/// Uses more memory than [`floyd`] or [`brent`] but never needs to step the simulation more than once,
+/// so it can be driven by a simulation loop that is also recording other things about each step.
+#[derive(Clone, Debug)]
+pub struct CycleDetector<S> {
+    seen: HashMap<S, usize>,
+}
+
+impl<S> CycleDetector<S>
+where
+    S: Hash + Eq + Clone,
+{
+    pub fn new() -> Self {
+        CycleDetector {
+            seen: HashMap::new(),
+        }
+    }
+
+    /// record the state at the next step, returning the cycle if this state has been seen before
+    pub fn record(&mut self, state: &S) -> Option<Cycle> {
+        let step = self.seen.len();
+        match self.seen.get(state) {
+            Some(&start) => Some(Cycle {
+                start,
+                length: step - start,
+            }),
+            None => {
+                self.seen.insert(state.clone(), step);
+                None
+            }
+        }
+    }
+
+    /// the number of distinct states recorded so far
+    pub fn steps(&self) -> usize {
+        self.seen.len()
+    }
+}
+
+impl<S> Default for CycleDetector<S>
+where
+    S: Hash + Eq + Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Find the state after `n` steps, skipping ahead as soon as the states start repeating
+pub fn nth_state<S, F>(initial: S, n: usize, mut step: F) -> S
+where
+    S: Hash + Eq + Clone,
+    F: FnMut(&S) -> S,
+{
+    let mut detector = CycleDetector::new();
+    let mut history = Vec::new();
+    let mut state = initial;
+
+    for _ in 0..n {
+        if let Some(cycle) = detector.record(&state) {
+            return history.swap_remove(cycle.equivalent_step(n));
+        }
+        let next = step(&state);
+        history.push(state);
+        state = next;
+    }
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 0, 1, 2, 3, ..., 10, 3, 4, ...
+    fn rho(x: &usize) -> usize {
+        if *x < 10 {
+            x + 1
+        } else {
+            3
+        }
+    }
+
+    /// a pseudo-random sequence with a less obvious cycle
+    fn pseudo_random(x: &u64) -> u64 {
+        (x * x + 1) % 255
+    }
+
+    fn naive<S: PartialEq + Clone>(initial: S, step: fn(&S) -> S) -> Cycle {
+        let mut history = vec![initial];
+        loop {
+            let next = step(history.last().unwrap());
+            if let Some(start) = history.iter().position(|s| *s == next) {
+                return Cycle {
+                    start,
+                    length: history.len() - start,
+                };
+            }
+            history.push(next);
+        }
+    }
+
+    #[test]
+    fn test_algorithms_agree() {
+        let expected = Cycle {
+            start: 3,
+            length: 8,
+        };
+        assert_eq!(naive(0, rho), expected);
+        assert_eq!(floyd(0, 100, rho), Some(expected));
+        assert_eq!(brent(0, 100, rho), Some(expected));
+
+        for initial in 0..255 {
+            let expected = naive(initial, pseudo_random);
+            assert_eq!(floyd(initial, 1000, pseudo_random), Some(expected));
+            assert_eq!(brent(initial, 1000, pseudo_random), Some(expected));
+
+            let mut detector = CycleDetector::new();
+            let mut state = initial;
+            let found = loop {
+                if let Some(cycle) = detector.record(&state) {
+                    break cycle;
+                }
+                state = pseudo_random(&state);
+            };
+            assert_eq!(found, expected);
+        }
+    }
+
+    #[test]
+    fn test_limit() {
+        assert_eq!(floyd(0, 2, rho), None);
+        assert_eq!(brent(0, 2, rho), None);
+    }
+
+    #[test]
+    fn test_nth_state() {
+        for n in 0..50 {
+            let mut expected = 0;
+            for _ in 0..n {
+                expected = rho(&expected);
+            }
+            assert_eq!(nth_state(0, n, rho), expected, "n = {n}");
+        }
+        assert_eq!(
+            nth_state(0, 1_000_000_000, rho),
+            3 + (1_000_000_000 - 3) % 8
+        );
+    }
+
+    #[test]
+    fn test_extrapolate_sum() {
+        let cycle = Cycle {
+            start: 3,
+            length: 8,
+        };
+        // the value produced by each step is just the state we stepped from
+        let per_step: Vec<u64> = (0..11).collect();
+        for n in 0..50 {
+            let mut expected = 0;
+            let mut state = 0;
+            for _ in 0..n {
+                expected += state as u64;
+                state = rho(&state);
+            }
+            assert_eq!(cycle.extrapolate_sum(&per_step, n), expected, "n = {n}");
+        }
+    }
+}
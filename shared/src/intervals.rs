@@ -0,0 +1,346 @@
+use std::{collections::BTreeMap, fmt, ops::Range};
+
+use crate::arithmetic::Integer;
+
+/// A set of integers stored as disjoint, half open spans `start..end`
+///
+/// Overlapping or touching spans are merged as they are inserted, so there is only ever one way to store the same set.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct IntervalSet<T> {
+    /// start -> end of each span, no two spans overlap or touch
+    spans: BTreeMap<T, T>,
+}
+
+impl<T: Integer> IntervalSet<T> {
+    pub fn new() -> Self {
+        IntervalSet {
+            spans: BTreeMap::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// the number of integers in the set
+    pub fn len(&self) -> T {
+        self.iter()
+            .fold(T::ZERO, |len, span| len + (span.end - span.start))
+    }
+
+    /// the number of disjoint spans making up the set
+    pub fn span_count(&self) -> usize {
+        self.spans.len()
+    }
+
+    /// iterate over the spans in the set, in order
+    pub fn iter(&self) -> impl Iterator<Item = Range<T>> + '_ {
+        self.spans.iter().map(|(&start, &end)| start..end)
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        self.span_containing(value)
+            .is_some_and(|span| span.end > value)
+    }
+
+    /// the span starting at or before `value`, which may have ended before it
+    fn span_containing(&self, value: T) -> Option<Range<T>> {
+        self.spans
+            .range(..=value)
+            .next_back()
+            .map(|(&start, &end)| start..end)
+    }
+
+    pub fn insert(&mut self, range: Range<T>) {
+        if range.is_empty() {
+            return;
+        }
+        let Range { mut start, mut end } = range;
+
+        // merge with a span that starts before and reaches this one
+        if let Some(before) = self.span_containing(start) {
+            if before.end >= start {
+                start = before.start;
+                end = end.max(before.end);
+            }
+        }
+        // swallow every span that starts inside or right at the end of this one
+        let swallowed: Vec<_> = self
+            .spans
+            .range(start..=end)
+            .map(|(&start, &end)| (start, end))
+            .collect();
+        for (span_start, span_end) in swallowed {
+            end = end.max(span_end);
+            self.spans.remove(&span_start);
+        }
+
+        self.spans.insert(start, end);
+    }
+
+    pub fn remove(&mut self, range: Range<T>) {
+        if range.is_empty() {
+            return;
+        }
+
+        // cut the end off a span that starts before this one
+        if let Some(before) = self.span_containing(range.start) {
+            if before.end > range.start && before.start < range.start {
+                self.spans.insert(before.start, range.start);
+                if before.end > range.end {
+                    self.spans.insert(range.end, before.end);
+                }
+            }
+        }
+        // cut the start off every span that starts inside this one
+        let overlapping: Vec<_> = self
+            .spans
+            .range(range.start..range.end)
+            .map(|(&start, &end)| (start, end))
+            .collect();
+        for (span_start, span_end) in overlapping {
+            self.spans.remove(&span_start);
+            if span_end > range.end {
+                self.spans.insert(range.end, span_end);
+            }
+        }
+    }
+
+    /// every integer in either set
+    pub fn union(&self, other: &Self) -> Self {
+        let mut union = self.clone();
+        for span in other.iter() {
+            union.insert(span);
+        }
+        union
+    }
+
+    /// every integer in both sets
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut intersection = Self::new();
+        let mut a = self.iter().peekable();
+        let mut b = other.iter().peekable();
+
+        while let (Some(x), Some(y)) = (a.peek().cloned(), b.peek().cloned()) {
+            let start = x.start.max(y.start);
+            let end = x.end.min(y.end);
+            if start < end {
+                intersection.spans.insert(start, end);
+            }
+            // move past whichever span finishes first, the other might overlap the next one
+            if x.end < y.end {
+                a.next();
+            } else {
+                b.next();
+            }
+        }
+        intersection
+    }
+
+    /// every integer within `bounds` that is not in the set
+    pub fn complement(&self, bounds: Range<T>) -> Self {
+        IntervalSet {
+            spans: self.gaps(bounds).map(|gap| (gap.start, gap.end)).collect(),
+        }
+    }
+
+    /// iterate over the spans within `bounds` that are not in the set, in order
+    pub fn gaps(&self, bounds: Range<T>) -> impl Iterator<Item = Range<T>> + '_ {
+        let Range { start, end } = bounds;
+        let mut cursor = start;
+
+        // start from the span that might cover the start of the bounds
+        let first = self.span_containing(start).map_or(start, |span| span.start);
+
+        self.spans
+            .range(first..)
+            .map(|(&start, &end)| Some(start..end))
+            .take_while(move |span| span.as_ref().is_some_and(|span| span.start < end))
+            // one last gap from the final span to the end of the bounds
+            .chain(std::iter::once(None))
+            .filter_map(move |span| {
+                let gap_end = span.as_ref().map_or(end, |span| span.start);
+                let gap = cursor..gap_end;
+                if let Some(span) = span {
+                    cursor = cursor.max(span.end);
+                }
+                (!gap.is_empty()).then_some(gap)
+            })
+    }
+
+    /// whether every integer in the set is also in `other`
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.iter().all(|span| {
+            other
+                .span_containing(span.start)
+                .is_some_and(|outer| outer.end >= span.end)
+        })
+    }
+
+    /// whether the sets have any integers in common
+    pub fn intersects(&self, other: &Self) -> bool {
+        !self.intersection(other).is_empty()
+    }
+}
+
+impl<T: Integer> Default for IntervalSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Integer> From<Range<T>> for IntervalSet<T> {
+    fn from(range: Range<T>) -> Self {
+        let mut set = Self::new();
+        set.insert(range);
+        set
+    }
+}
+
+impl<T: Integer> FromIterator<Range<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Range<T>>>(iter: I) -> Self {
+        let mut set = Self::new();
+        for range in iter {
+            set.insert(range);
+        }
+        set
+    }
+}
+
+impl<T: Integer> Extend<Range<T>> for IntervalSet<T> {
+    fn extend<I: IntoIterator<Item = Range<T>>>(&mut self, iter: I) {
+        for range in iter {
+            self.insert(range);
+        }
+    }
+}
+
+impl<T: Integer> fmt::Debug for IntervalSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+    use crate::testing::Lcg;
+
+    fn set(spans: &[Range<i32>]) -> IntervalSet<i32> {
+        spans.iter().cloned().collect()
+    }
+
+    fn spans(set: &IntervalSet<i32>) -> Vec<Range<i32>> {
+        set.iter().collect()
+    }
+
+    #[test]
+    // the vecs are lists of spans, not ranges to be expanded
+    #[allow(clippy::single_range_in_vec_init)]
+    fn test_insert_merges() {
+        let mut total = IntervalSet::new();
+        let inserts = [
+            (0..2, vec![0..2]),
+            (4..6, vec![0..2, 4..6]),
+            (8..10, vec![0..2, 4..6, 8..10]),
+            // touching spans are merged
+            (2..4, vec![0..6, 8..10]),
+            (1..5, vec![0..6, 8..10]),
+            (5..8, vec![0..10]),
+            (-5..-5, vec![0..10]),
+        ];
+        for (range, expected) in inserts {
+            total.insert(range);
+            assert_eq!(spans(&total), expected);
+        }
+        assert_eq!(total.len(), 10);
+        assert_eq!(total.span_count(), 1);
+    }
+
+    #[test]
+    fn test_remove_splits() {
+        let mut total = set(&[0..10, 20..30]);
+        total.remove(3..5);
+        assert_eq!(spans(&total), vec![0..3, 5..10, 20..30]);
+        total.remove(8..22);
+        assert_eq!(spans(&total), vec![0..3, 5..8, 22..30]);
+        total.remove(-10..4);
+        assert_eq!(spans(&total), vec![5..8, 22..30]);
+        total.remove(5..8);
+        assert_eq!(spans(&total), vec![22..30]);
+        assert_eq!(total.len(), 8);
+    }
+
+    #[test]
+    fn test_gaps() {
+        let total = set(&[0..5, 7..9, 12..20]);
+        assert_eq!(total.gaps(0..20).collect::<Vec<_>>(), vec![5..7, 9..12]);
+        assert_eq!(
+            total.gaps(-3..25).collect::<Vec<_>>(),
+            vec![-3..0, 5..7, 9..12, 20..25]
+        );
+        assert_eq!(total.gaps(2..8).collect::<Vec<_>>(), vec![5..7]);
+        assert_eq!(total.gaps(13..18).next(), None);
+        assert_eq!(spans(&total.complement(3..10)), vec![5..7, 9..10]);
+    }
+
+    #[test]
+    fn test_subset() {
+        let outer = set(&[0..10, 20..30]);
+        assert!(set(&[2..4, 25..30]).is_subset(&outer));
+        assert!(!IntervalSet::from(8..12).is_subset(&outer));
+        assert!(IntervalSet::new().is_subset(&outer));
+        assert!(outer.intersects(&IntervalSet::from(9..20)));
+        assert!(!outer.intersects(&IntervalSet::from(10..20)));
+    }
+
+    fn random_range(rng: &mut Lcg) -> Range<i32> {
+        let start = rng.below(60) as i32 - 10;
+        start..start + rng.below(15) as i32
+    }
+
+    fn naive(set: &IntervalSet<i32>) -> BTreeSet<i32> {
+        set.iter().flatten().collect()
+    }
+
+    #[test]
+    fn test_matches_naive() {
+        let mut rng = Lcg::new(35);
+        for _ in 0..200 {
+            let mut a = IntervalSet::new();
+            let mut b = IntervalSet::new();
+            let mut naive_a = BTreeSet::new();
+            let mut naive_b = BTreeSet::new();
+            for _ in 0..6 {
+                let range = random_range(&mut rng);
+                naive_a.extend(range.clone());
+                a.insert(range);
+                let range = random_range(&mut rng);
+                naive_b.extend(range.clone());
+                b.insert(range);
+            }
+            let range = random_range(&mut rng);
+            for x in range.clone() {
+                naive_a.remove(&x);
+            }
+            a.remove(range);
+
+            assert_eq!(naive(&a), naive_a);
+            assert_eq!(a.len() as usize, naive_a.len());
+            // spans never touch, so there is only one way to store the set
+            assert!(a.iter().zip(a.iter().skip(1)).all(|(x, y)| x.end < y.start));
+
+            assert_eq!(naive(&a.union(&b)), &naive_a | &naive_b);
+            assert_eq!(naive(&a.intersection(&b)), &naive_a & &naive_b);
+            assert_eq!(a.is_subset(&b), naive_a.is_subset(&naive_b));
+            assert_eq!(a.intersects(&b), !naive_a.is_disjoint(&naive_b));
+
+            let bounds = -20..70;
+            let expected: BTreeSet<i32> = bounds.clone().filter(|x| !naive_a.contains(x)).collect();
+            assert_eq!(naive(&a.complement(bounds)), expected);
+            assert!((-20..70).all(|x| a.contains(x) == naive_a.contains(&x)));
+        }
+    }
+}
@@ -8,10 +8,20 @@ use std::{
 };
 
 pub mod arithmetic;
+#[cfg(feature = "cycles")]
+pub mod cycles;
+#[cfg(feature = "intervals")]
+pub mod intervals;
+#[cfg(feature = "parallel")]
+pub mod parallel;
 #[cfg(feature = "pathfinding")]
 pub mod pathfinding;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 #[cfg(feature = "types_2d")]
 pub mod types_2d;
+#[cfg(feature = "types_3d")]
+pub mod types_3d;
 
 #[cfg(feature = "parsing")]
 pub use parsely as parsing;
@@ -21,7 +31,7 @@ pub use indoc::indoc;
 /// Generic error for when the value of something is wrong
 ///
 /// Fine for simple parsing of types from Strings
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct ValueError<T: fmt::Display + fmt::Debug>(pub T);
 
 impl<T> std::error::Error for ValueError<T> where T: fmt::Display + fmt::Debug {}
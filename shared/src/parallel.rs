@@ -0,0 +1,123 @@
+use std::{num::NonZeroUsize, ops::Range, sync::Mutex, thread};
+
+use crate::arithmetic::Integer;
+
+/// Find the lowest value in `range` for which `f` returns `Some`, splitting the search across every available thread
+///
+/// See [`find_map_with_threads`].
+pub fn find_map<T, R, F>(range: Range<T>, chunk_size: T, f: F) -> Option<R>
+where
+    T: Integer + Send + Sync,
+    R: Send,
+    F: Fn(T) -> Option<R> + Sync,
+{
+    let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    find_map_with_threads(range, chunk_size, threads, f)
+}
+
+/// Find the lowest value in `range` for which `f` returns `Some`, splitting the search across `threads` threads
+///
+/// Each thread takes the next chunk of `chunk_size` values in turn. Once any thread has found something,
+/// the threads stop taking chunks after it, but finish the chunks before it in case there is an earlier result.
+/// So the result is always the same one that searching the range in order would find.
+pub fn find_map_with_threads<T, R, F>(
+    range: Range<T>,
+    chunk_size: T,
+    threads: usize,
+    f: F,
+) -> Option<R>
+where
+    T: Integer + Send + Sync,
+    R: Send,
+    F: Fn(T) -> Option<R> + Sync,
+{
+    assert!(chunk_size > T::ZERO, "chunk_size must be positive");
+
+    let next_chunk = Mutex::new(range.start);
+    let found: Mutex<Option<(T, R)>> = Mutex::new(None);
+
+    let take_chunk = || -> Option<Range<T>> {
+        let mut next_chunk = next_chunk.lock().expect("no worker panicked");
+        let start = *next_chunk;
+        let already_found =
+            matches!(*found.lock().expect("no worker panicked"), Some((at, _)) if at < start);
+        if start >= range.end || already_found {
+            return None;
+        }
+        let end = start
+            .checked_add(chunk_size)
+            .map_or(range.end, |end| end.min(range.end));
+        *next_chunk = end;
+        Some(start..end)
+    };
+
+    let search = || {
+        while let Some(Range { start, end }) = take_chunk() {
+            let mut value = start;
+            while value < end {
+                if let Some(result) = f(value) {
+                    let mut found = found.lock().expect("no worker panicked");
+                    if found.as_ref().is_none_or(|(at, _)| value < *at) {
+                        *found = Some((value, result));
+                    }
+                    break;
+                }
+                value = value + T::ONE;
+            }
+        }
+    };
+
+    thread::scope(|scope| {
+        for _ in 1..threads {
+            scope.spawn(search);
+        }
+        // this thread can search too rather than just waiting
+        search();
+    });
+
+    found
+        .into_inner()
+        .expect("no worker panicked")
+        .map(|(_, result)| result)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn test_finds_lowest() {
+        for threads in [1, 2, 4, 7] {
+            for chunk_size in [1, 3, 10, 1000] {
+                let found = find_map_with_threads(0..1000, chunk_size, threads, |x: i32| {
+                    (x % 97 == 42 || x == 501).then_some(x * 2)
+                });
+                assert_eq!(found, Some(84), "{threads} threads, chunks of {chunk_size}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_not_found() {
+        assert_eq!(find_map(-50..50isize, 7, |_| None::<()>), None);
+        assert_eq!(find_map(10..10u8, 7, Some), None);
+        assert_eq!(
+            find_map(250..u8::MAX, 100, |x| (x == 254).then_some(x)),
+            Some(254)
+        );
+    }
+
+    #[test]
+    fn test_stops_early() {
+        let calls = AtomicUsize::new(0);
+        let found = find_map_with_threads(0..1_000_000u64, 100, 4, |x| {
+            calls.fetch_add(1, Ordering::Relaxed);
+            (x == 150).then_some(x)
+        });
+        assert_eq!(found, Some(150));
+        // the threads might each finish a few more chunks before they notice, but not the whole range
+        assert!(calls.load(Ordering::Relaxed) < 100_000);
+    }
+}
@@ -0,0 +1,31 @@
+//! Helpers for tests and benchmarks, not for solutions
+//!
+//! only built for shared's own tests, or for other crates that enable the `testing` feature in their dev-dependencies
+
+/// A small deterministic pseudo-random number generator, so property tests and benchmarks are reproducible
+///
+/// iterating it yields the raw numbers, which are 31 bits
+pub struct Lcg(u64);
+
+impl Lcg {
+    pub fn new(seed: u64) -> Self {
+        Lcg(seed)
+    }
+
+    /// a number in `0..below`
+    pub fn below(&mut self, below: u64) -> u64 {
+        self.next().expect("never runs out") % below
+    }
+}
+
+impl Iterator for Lcg {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        Some(self.0 >> 33)
+    }
+}
@@ -1,15 +1,26 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fmt,
+    hash::Hash,
     iter::FusedIterator,
-    ops::{Add, AddAssign, Mul, MulAssign, Sub},
+    ops::{Add, AddAssign, Index, IndexMut, Mul, MulAssign, Range, Sub},
+    str::FromStr,
 };
 
 pub use itertools::Itertools;
 
 use Direction::*;
 
-use crate::arithmetic::gcd;
+use crate::{arithmetic::gcd, ValueError};
+
+mod ray;
+mod transform;
+mod visibility;
+pub mod visualisation;
+
+pub use ray::{Ray, TakeUntilInclusive};
+pub use transform::GridView;
+pub use visibility::Visibility;
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum Direction {
@@ -19,8 +30,64 @@ pub enum Direction {
     Right,
 }
 
+/// A turn relative to the way something is facing, for walking around a grid turtle-style
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Turn {
+    /// anticlockwise
+    Left,
+    /// clockwise
+    Right,
+    Around,
+    Straight,
+}
+
+/// How to write a [`Direction`] as text
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum DirectionStyle {
+    /// `U`, `D`, `L` and `R`
+    #[default]
+    Letter,
+    /// `^`, `v`, `<` and `>`
+    Arrow,
+    /// `N`, `S`, `W` and `E`
+    Compass,
+    /// `Up`, `Down`, `Left` and `Right`
+    Name,
+}
+
 impl Direction {
-    fn turn_clockwise(&self) -> Self {
+    /// every direction, clockwise starting from Up
+    pub fn all() -> Directions {
+        directions_clockwise(Up)
+    }
+
+    pub fn opposite(&self) -> Self {
+        match self {
+            Up => Down,
+            Down => Up,
+            Left => Right,
+            Right => Left,
+        }
+    }
+
+    pub fn turn(&self, turn: Turn) -> Self {
+        match turn {
+            Turn::Left => self.turn_anticlockwise(),
+            Turn::Right => self.turn_clockwise(),
+            Turn::Around => self.opposite(),
+            Turn::Straight => *self,
+        }
+    }
+
+    /// display the direction in the given style, plain `{}` formatting uses [`DirectionStyle::Letter`]
+    pub fn display(&self, style: DirectionStyle) -> DisplayDirection {
+        DisplayDirection {
+            direction: *self,
+            style,
+        }
+    }
+
+    pub fn turn_clockwise(&self) -> Self {
         match self {
             Up => Right,
             Right => Down,
@@ -29,7 +96,7 @@ impl Direction {
         }
     }
 
-    fn turn_anticlockwise(&self) -> Self {
+    pub fn turn_anticlockwise(&self) -> Self {
         match self {
             Up => Left,
             Left => Down,
@@ -37,6 +104,87 @@ impl Direction {
             Right => Up,
         }
     }
+
+    /// turn 45° clockwise, which always ends up on a diagonal
+    pub fn turn_clockwise_45(&self) -> Direction8 {
+        Direction8::from(*self).turn_clockwise_45()
+    }
+
+    /// turn 45° anticlockwise, which always ends up on a diagonal
+    pub fn turn_anticlockwise_45(&self) -> Direction8 {
+        Direction8::from(*self).turn_anticlockwise_45()
+    }
+}
+
+/// parses any of the styles in [`DirectionStyle`] except [`DirectionStyle::Name`]
+impl TryFrom<char> for Direction {
+    type Error = ValueError<char>;
+
+    fn try_from(ch: char) -> Result<Self, Self::Error> {
+        match ch {
+            'U' | '^' | 'N' => Ok(Up),
+            'D' | 'v' | 'S' => Ok(Down),
+            'L' | '<' | 'W' => Ok(Left),
+            'R' | '>' | 'E' => Ok(Right),
+            _ => Err(ValueError(ch)),
+        }
+    }
+}
+
+/// parses a single character like [`Direction::try_from`], or a name like `Up`
+impl FromStr for Direction {
+    type Err = ValueError<String>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(ch), None) => Direction::try_from(ch).map_err(|_| ValueError(s.to_string())),
+            _ => match s {
+                "Up" => Ok(Up),
+                "Down" => Ok(Down),
+                "Left" => Ok(Left),
+                "Right" => Ok(Right),
+                _ => Err(ValueError(s.to_string())),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.display(DirectionStyle::default()), f)
+    }
+}
+
+/// This struct is returned by the display method of [`Direction`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DisplayDirection {
+    direction: Direction,
+    style: DirectionStyle,
+}
+
+impl fmt::Display for DisplayDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match (self.style, self.direction) {
+            (DirectionStyle::Letter, Up) => "U",
+            (DirectionStyle::Letter, Down) => "D",
+            (DirectionStyle::Letter, Left) => "L",
+            (DirectionStyle::Letter, Right) => "R",
+            (DirectionStyle::Arrow, Up) => "^",
+            (DirectionStyle::Arrow, Down) => "v",
+            (DirectionStyle::Arrow, Left) => "<",
+            (DirectionStyle::Arrow, Right) => ">",
+            (DirectionStyle::Compass, Up) => "N",
+            (DirectionStyle::Compass, Down) => "S",
+            (DirectionStyle::Compass, Left) => "W",
+            (DirectionStyle::Compass, Right) => "E",
+            (DirectionStyle::Name, Up) => "Up",
+            (DirectionStyle::Name, Down) => "Down",
+            (DirectionStyle::Name, Left) => "Left",
+            (DirectionStyle::Name, Right) => "Right",
+        };
+        f.write_str(text)
+    }
 }
 
 pub fn directions_clockwise(start: Direction) -> Directions {
@@ -83,6 +231,150 @@ impl Iterator for Directions {
     }
 }
 
+/// A direction including the diagonals between the four [`Direction`]s
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Direction8 {
+    Up,
+    UpRight,
+    Right,
+    DownRight,
+    Down,
+    DownLeft,
+    Left,
+    UpLeft,
+}
+
+impl Direction8 {
+    /// every direction, clockwise starting from Up
+    pub const ALL: [Direction8; 8] = [
+        Direction8::Up,
+        Direction8::UpRight,
+        Direction8::Right,
+        Direction8::DownRight,
+        Direction8::Down,
+        Direction8::DownLeft,
+        Direction8::Left,
+        Direction8::UpLeft,
+    ];
+
+    /// where this direction is in [`Direction8::ALL`]
+    fn index(&self) -> usize {
+        *self as usize
+    }
+
+    pub fn turn_clockwise_45(&self) -> Self {
+        Self::ALL[(self.index() + 1) % 8]
+    }
+
+    pub fn turn_anticlockwise_45(&self) -> Self {
+        Self::ALL[(self.index() + 7) % 8]
+    }
+
+    pub fn turn_clockwise(&self) -> Self {
+        Self::ALL[(self.index() + 2) % 8]
+    }
+
+    pub fn turn_anticlockwise(&self) -> Self {
+        Self::ALL[(self.index() + 6) % 8]
+    }
+
+    pub fn is_diagonal(&self) -> bool {
+        self.index() % 2 == 1
+    }
+
+    /// the [`Direction`] this is, unless it is diagonal
+    pub fn orthogonal(&self) -> Option<Direction> {
+        match self {
+            Direction8::Up => Some(Up),
+            Direction8::Right => Some(Right),
+            Direction8::Down => Some(Down),
+            Direction8::Left => Some(Left),
+            _ => None,
+        }
+    }
+
+    /// the direction a single step towards `vector` goes in, which is diagonal unless `vector` is along an axis
+    ///
+    /// `None` for the zero vector, which doesn't point anywhere
+    pub fn towards(vector: Vector) -> Option<Self> {
+        match (vector.x.signum(), vector.y.signum()) {
+            (0, -1) => Some(Direction8::Up),
+            (1, -1) => Some(Direction8::UpRight),
+            (1, 0) => Some(Direction8::Right),
+            (1, 1) => Some(Direction8::DownRight),
+            (0, 1) => Some(Direction8::Down),
+            (-1, 1) => Some(Direction8::DownLeft),
+            (-1, 0) => Some(Direction8::Left),
+            (-1, -1) => Some(Direction8::UpLeft),
+            _ => None,
+        }
+    }
+}
+
+impl From<Direction> for Direction8 {
+    fn from(direction: Direction) -> Self {
+        match direction {
+            Up => Direction8::Up,
+            Right => Direction8::Right,
+            Down => Direction8::Down,
+            Left => Direction8::Left,
+        }
+    }
+}
+
+/// iter all 8 directions, turning 45° clockwise each time
+pub fn directions8_clockwise(start: Direction8) -> impl Iterator<Item = Direction8> {
+    std::iter::successors(Some(start), |direction| Some(direction.turn_clockwise_45())).take(8)
+}
+
+/// iter all 8 directions, turning 45° anticlockwise each time
+pub fn directions8_anticlockwise(start: Direction8) -> impl Iterator<Item = Direction8> {
+    std::iter::successors(Some(start), |direction| {
+        Some(direction.turn_anticlockwise_45())
+    })
+    .take(8)
+}
+
+/// Which positions around a cell count as its neighbours
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Neighbourhood {
+    /// the 4 orthogonally adjacent positions, clockwise from Up
+    VonNeumann,
+    /// all 8 surrounding positions including diagonals, clockwise from Up
+    Moore,
+    /// any offsets, in the order given
+    Custom(Vec<Vector>),
+}
+
+const VON_NEUMANN: [Vector; 4] = [
+    Vector { x: 0, y: -1 },
+    Vector { x: 1, y: 0 },
+    Vector { x: 0, y: 1 },
+    Vector { x: -1, y: 0 },
+];
+
+const MOORE: [Vector; 8] = [
+    Vector { x: 0, y: -1 },
+    Vector { x: 1, y: -1 },
+    Vector { x: 1, y: 0 },
+    Vector { x: 1, y: 1 },
+    Vector { x: 0, y: 1 },
+    Vector { x: -1, y: 1 },
+    Vector { x: -1, y: 0 },
+    Vector { x: -1, y: -1 },
+];
+
+impl Neighbourhood {
+    /// the offset from a cell to each of its neighbours
+    pub fn offsets(&self) -> &[Vector] {
+        match self {
+            Neighbourhood::VonNeumann => &VON_NEUMANN,
+            Neighbourhood::Moore => &MOORE,
+            Neighbourhood::Custom(offsets) => offsets,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub struct Coords {
     pub x: usize,
@@ -205,19 +497,38 @@ impl Vector {
             Vector { x, y: 0 } if x < 0 => Vector { x: -1, y: 0 },
             Vector { x: 0, y } if y < 0 => Vector { x: 0, y: -1 },
             Vector { x: 0, y: 0 } => Vector { x: 0, y: 0 },
-            Vector { x, y } => {
-                let factor = gcd(modulus(x), modulus(y)) as isize;
-                Vector {
-                    x: self.x / factor,
-                    y: self.y / factor,
-                }
-            }
+            Vector { x, y } => match isize::try_from(gcd(x, y)) {
+                Ok(factor) => Vector {
+                    x: x / factor,
+                    y: y / factor,
+                },
+                // the gcd only fails to fit in an isize when x and y are both isize::MIN
+                Err(_) => Vector {
+                    x: x.signum(),
+                    y: y.signum(),
+                },
+            },
         }
     }
 
     pub fn manhattan_distance(&self, other: Vector) -> usize {
         modulus(other.x - self.x) + modulus(other.y - self.y)
     }
+
+    /// rotate 45° into (u, v) = (x + y, x - y) space, where diamonds become axis aligned squares
+    pub fn to_rotated(self) -> Vector {
+        Vector::new(self.x + self.y, self.x - self.y)
+    }
+
+    /// rotate back from (u, v) space, which is only possible if u and v are both even or both odd
+    pub fn from_rotated(self) -> Option<Vector> {
+        let Vector { x: u, y: v } = self;
+        if (u - v) % 2 == 0 {
+            Some(Vector::new((u + v) / 2, (u - v) / 2))
+        } else {
+            None
+        }
+    }
 }
 
 impl From<Direction> for Vector {
@@ -231,6 +542,12 @@ impl From<Direction> for Vector {
     }
 }
 
+impl From<Direction8> for Vector {
+    fn from(direction: Direction8) -> Self {
+        MOORE[direction.index()]
+    }
+}
+
 impl Add for Vector {
     type Output = Self;
 
@@ -311,10 +628,7 @@ impl Add<Vector> for Coords {
 }
 
 pub fn modulus(n: isize) -> usize {
-    match n {
-        n if n < 0 => -n as usize,
-        _ => n as usize,
-    }
+    n.unsigned_abs()
 }
 
 impl Sub<Coords> for Coords {
@@ -379,58 +693,165 @@ impl From<Coords> for Size {
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
-pub struct GridCell<T> {
-    pub value: Option<T>,
-    pub visited: bool,
+/// Every position within a manhattan distance of the center (a ball in the L1 norm)
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Diamond {
+    pub center: Vector,
+    pub radius: usize,
 }
 
-impl<T> Default for GridCell<T> {
-    fn default() -> Self {
-        GridCell {
-            value: None,
-            visited: false,
+impl Diamond {
+    pub fn new(center: Vector, radius: usize) -> Self {
+        Diamond { center, radius }
+    }
+
+    pub fn contains(&self, position: Vector) -> bool {
+        self.center.manhattan_distance(position) <= self.radius
+    }
+
+    /// the x positions inside the diamond in row `y`
+    pub fn row(&self, y: isize) -> Option<Range<isize>> {
+        let spare = self.radius.checked_sub(modulus(self.center.y - y))? as isize;
+        Some(self.center.x - spare..self.center.x + spare + 1)
+    }
+
+    /// the y positions inside the diamond in column `x`
+    pub fn column(&self, x: isize) -> Option<Range<isize>> {
+        let spare = self.radius.checked_sub(modulus(self.center.x - x))? as isize;
+        Some(self.center.y - spare..self.center.y + spare + 1)
+    }
+
+    /// the smallest axis aligned rectangle containing the diamond
+    pub fn bounds(&self) -> Bounds {
+        let radius = self.radius as isize;
+        Bounds {
+            top_left: Vector::new(self.center.x - radius, self.center.y - radius),
+            bottom_right: Vector::new(self.center.x + radius, self.center.y + radius),
         }
     }
-}
 
-impl<T> GridCell<T> {
-    pub fn new(value: T) -> Self {
-        GridCell {
-            value: Some(value),
-            visited: false,
+    /// every position exactly `radius` from the center, clockwise from the top
+    pub fn boundary(&self) -> impl Iterator<Item = Vector> {
+        let Diamond { center, radius } = *self;
+        let r = radius as isize;
+        let corners = [
+            (Vector::new(0, -r), Vector::new(1, 1)),
+            (Vector::new(r, 0), Vector::new(-1, 1)),
+            (Vector::new(0, r), Vector::new(-1, -1)),
+            (Vector::new(-r, 0), Vector::new(1, -1)),
+        ];
+        // a diamond of radius 0 is just the center, rather than nothing at all
+        let count = (4 * radius).max(1);
+        (0..count).map(move |i| {
+            let (corner, step) = corners[i / radius.max(1)];
+            center + corner + step * (i % radius.max(1)) as isize
+        })
+    }
+
+    /// the diamond as an axis aligned square in rotated space, see [`Vector::to_rotated`]
+    pub fn to_rotated(&self) -> Bounds {
+        let radius = self.radius as isize;
+        let center = self.center.to_rotated();
+        Bounds {
+            top_left: Vector::new(center.x - radius, center.y - radius),
+            bottom_right: Vector::new(center.x + radius, center.y + radius),
+        }
+    }
+
+    /// the diamond that becomes `square` in rotated space, if there is one
+    ///
+    /// the square's sides must be the same length, and its center must rotate back to a whole position
+    pub fn from_rotated(square: Bounds) -> Option<Diamond> {
+        let Bounds {
+            top_left,
+            bottom_right,
+        } = square;
+        let width = bottom_right.x - top_left.x;
+        if width != bottom_right.y - top_left.y || width < 0 || width % 2 != 0 {
+            return None;
+        }
+        let center = Vector::new(top_left.x + width / 2, top_left.y + width / 2).from_rotated()?;
+        Some(Diamond::new(center, width as usize / 2))
+    }
+
+    /// the positions in both diamonds, which is a rectangle in rotated space but not always a diamond
+    ///
+    /// some corners of the rectangle might not rotate back to whole positions
+    pub fn intersection(&self, other: &Diamond) -> Option<Bounds> {
+        let a = self.to_rotated();
+        let b = other.to_rotated();
+        let top_left = Vector::new(
+            a.top_left.x.max(b.top_left.x),
+            a.top_left.y.max(b.top_left.y),
+        );
+        let bottom_right = Vector::new(
+            a.bottom_right.x.min(b.bottom_right.x),
+            a.bottom_right.y.min(b.bottom_right.y),
+        );
+        if top_left.x > bottom_right.x || top_left.y > bottom_right.y {
+            None
+        } else {
+            Some(Bounds {
+                top_left,
+                bottom_right,
+            })
         }
     }
 }
 
-#[derive(Clone, Eq, PartialEq)]
-pub struct InfGrid<T> {
-    cells: HashMap<Vector, GridCell<T>>,
-    pub top_left: Vector,
-    pub bottom_right: Vector,
+/// The layer an [`InfGrid`] marks positions in unless it is given its own layer type
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Visited;
+
+/// A sparse grid that grows in every direction as cells are added
+///
+/// As well as a value in each cell, positions can be marked in any number of layers, whether or not they have a value.
+/// The layers are named by `L`, which is usually a fieldless enum so that a misspelled layer doesn't compile.
+/// Its bounds are always the smallest rectangle containing every value and mark, or just the origin it was created
+/// with while it is empty.
+#[derive(Clone)]
+pub struct InfGrid<T, L = Visited> {
+    cells: HashMap<Vector, T>,
+    /// the positions marked in each layer, which never holds an empty set
+    layers: HashMap<L, HashSet<Vector>>,
+    /// contains every value and mark, but may be too big if `stale` is set
+    top_left: Vector,
+    bottom_right: Vector,
+    /// where the bounds are while the grid is empty
+    origin: Vector,
+    /// something on the edge of the bounds has been removed, so they might need to shrink
+    stale: bool,
+}
+
+impl<T: PartialEq, L: Eq + Hash> PartialEq for InfGrid<T, L> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cells == other.cells && self.layers == other.layers && self.bounds() == other.bounds()
+    }
 }
 
-impl<T: fmt::Debug> fmt::Debug for InfGrid<T> {
+impl<T: Eq, L: Eq + Hash> Eq for InfGrid<T, L> {}
+
+/// values are written with their `Debug`, positions that are only marked in a layer as `,` and empty positions as `.`
+impl<T: fmt::Debug, L> fmt::Debug for InfGrid<T, L> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Bounds {
+            top_left,
+            bottom_right,
+        } = self.bounds();
         write!(
             f,
             "InfGrid {{ top_left: {:?}, bottom_right: {:?} }}",
-            self.top_left, self.bottom_right
+            top_left, bottom_right
         )?;
 
         writeln!(f)?;
-        let max_x = self.bottom_right.x;
+        let max_x = bottom_right.x;
         for position in self.positions() {
-            write!(
-                f,
-                "{}",
-                self.get(position)
-                    .map(|x| match &x.value {
-                        Some(x) => format!("{x:?}"),
-                        None => String::from(","),
-                    })
-                    .unwrap_or_else(|| ".".into())
-            )?;
+            match self.get(position) {
+                Some(value) => write!(f, "{value:?}")?,
+                None if self.is_marked_in_any(position) => write!(f, ",")?,
+                None => write!(f, ".")?,
+            }
             if position.x == max_x {
                 writeln!(f)?;
             }
@@ -441,115 +862,423 @@ impl<T: fmt::Debug> fmt::Debug for InfGrid<T> {
 
 impl<T> InfGrid<T> {
     pub fn new() -> Self {
-        InfGrid {
-            cells: HashMap::new(),
-            top_left: Vector { x: 0, y: 0 },
-            bottom_right: Vector { x: 0, y: 0 },
-        }
+        Self::new_around(Vector::zero())
     }
 
     pub fn new_off_center(center: Coords) -> Self {
+        Self::new_around(Vector::from(center))
+    }
+}
+
+impl<T, L> InfGrid<T, L> {
+    /// an empty grid with its own type of layers, see [`InfGrid::mark`]
+    pub fn with_layers() -> Self {
+        Self::new_around(Vector::zero())
+    }
+
+    /// an empty grid whose bounds are just `origin` until something is added
+    fn new_around(origin: Vector) -> Self {
         InfGrid {
             cells: HashMap::new(),
-            top_left: Vector::from(center),
-            bottom_right: Vector::from(center),
+            layers: HashMap::new(),
+            top_left: origin,
+            bottom_right: origin,
+            origin,
+            stale: false,
         }
     }
 
-    pub fn get(&self, position: Vector) -> Option<&GridCell<T>> {
+    pub fn get(&self, position: Vector) -> Option<&T> {
         self.cells.get(&position)
     }
 
-    pub fn get_mut(&mut self, position: Vector) -> Option<&mut GridCell<T>> {
+    pub fn get_mut(&mut self, position: Vector) -> Option<&mut T> {
         self.cells.get_mut(&position)
     }
 
-    pub fn add(&mut self, position: Vector, value: T, visited: bool) {
-        self.cells.insert(
+    /// the orthogonally adjacent positions that have a value
+    pub fn neighbours(&self, position: Vector) -> impl Iterator<Item = Vector> + '_ {
+        self.neighbours_at(position, &VON_NEUMANN)
+    }
+
+    /// the adjacent positions that have a value, including diagonals
+    pub fn neighbours8(&self, position: Vector) -> impl Iterator<Item = Vector> + '_ {
+        self.neighbours_at(position, &MOORE)
+    }
+
+    /// the positions in `neighbourhood` around `position` that have a value
+    pub fn neighbours_in<'a>(
+        &'a self,
+        position: Vector,
+        neighbourhood: &'a Neighbourhood,
+    ) -> impl Iterator<Item = Vector> + 'a {
+        self.neighbours_at(position, neighbourhood.offsets())
+    }
+
+    fn neighbours_at<'a>(
+        &'a self,
+        position: Vector,
+        offsets: &'a [Vector],
+    ) -> impl Iterator<Item = Vector> + 'a {
+        offsets
+            .iter()
+            .map(move |&offset| position + offset)
+            .filter(|neighbour| self.cells.contains_key(neighbour))
+    }
+
+    pub fn add(&mut self, position: Vector, value: T) {
+        self.insert(position, value);
+    }
+
+    /// put `value` at `position`, returning the value that was there before
+    pub fn insert(&mut self, position: Vector, value: T) -> Option<T> {
+        self.extend_bounds(position);
+        self.cells.insert(position, value)
+    }
+
+    /// take the value at `position` out of the grid, leaving any marks there alone
+    pub fn remove(&mut self, position: Vector) -> Option<T> {
+        let value = self.cells.remove(&position)?;
+        self.shrink_bounds(position);
+        Some(value)
+    }
+
+    /// the cell at `position`, to insert or update it in place
+    pub fn entry(&mut self, position: Vector) -> Entry<'_, T, L> {
+        Entry {
+            grid: self,
             position,
-            GridCell {
-                value: Some(value),
-                visited,
-            },
-        );
-        if position.x < self.top_left.x {
-            self.top_left.x = position.x;
-        }
-        if position.x > self.bottom_right.x {
-            self.bottom_right.x = position.x;
         }
-        if position.y < self.top_left.y {
-            self.top_left.y = position.y;
+    }
+
+    /// whether `position` is marked in any layer
+    pub fn is_marked_in_any(&self, position: Vector) -> bool {
+        self.layers
+            .values()
+            .any(|marked| marked.contains(&position))
+    }
+
+    /// grow the bounds to fit something new at `position`
+    fn extend_bounds(&mut self, position: Vector) {
+        if self.is_empty() {
+            self.top_left = position;
+            self.bottom_right = position;
+            self.stale = false;
+        } else {
+            self.top_left.x = self.top_left.x.min(position.x);
+            self.top_left.y = self.top_left.y.min(position.y);
+            self.bottom_right.x = self.bottom_right.x.max(position.x);
+            self.bottom_right.y = self.bottom_right.y.max(position.y);
         }
-        if position.y > self.bottom_right.y {
-            self.bottom_right.y = position.y;
+    }
+
+    /// something has been removed from `position`, so the bounds might be too big now
+    fn shrink_bounds(&mut self, position: Vector) {
+        if position.x == self.top_left.x
+            || position.y == self.top_left.y
+            || position.x == self.bottom_right.x
+            || position.y == self.bottom_right.y
+        {
+            // working out the new bounds means looking at everything, so wait until they're needed
+            self.stale = true;
         }
     }
 
-    pub fn add_skip_bounds(&mut self, position: Vector, value: T, visited: bool) {
-        self.cells.insert(
-            position,
-            GridCell {
-                value: Some(value),
-                visited,
+    /// the bounds of the values and marks, worked out from scratch
+    fn fit_bounds(&self) -> Bounds {
+        let mut positions = self.cells.keys().chain(self.layers.values().flatten());
+        let Some(&first) = positions.next() else {
+            return Bounds {
+                top_left: self.origin,
+                bottom_right: self.origin,
+            };
+        };
+        positions.fold(
+            Bounds {
+                top_left: first,
+                bottom_right: first,
             },
-        );
+            |Bounds {
+                 top_left,
+                 bottom_right,
+             },
+             position| Bounds {
+                top_left: Vector::new(top_left.x.min(position.x), top_left.y.min(position.y)),
+                bottom_right: Vector::new(
+                    bottom_right.x.max(position.x),
+                    bottom_right.y.max(position.y),
+                ),
+            },
+        )
+    }
+
+    /// shrink the bounds after things have been removed from the edges, and free the memory they used
+    ///
+    /// the bounds are always right without this, but they have to be worked out again every time until it's called
+    pub fn shrink_to_fit_bounds(&mut self) {
+        if self.stale {
+            let Bounds {
+                top_left,
+                bottom_right,
+            } = self.fit_bounds();
+            self.top_left = top_left;
+            self.bottom_right = bottom_right;
+            self.stale = false;
+        }
+        self.cells.shrink_to_fit();
+        for marked in self.layers.values_mut() {
+            marked.shrink_to_fit();
+        }
     }
 
     /// iter every Coord from left to right and top to bottom
     pub fn positions(&self) -> impl Iterator<Item = Vector> {
-        iter_positions(&self.top_left, &self.bottom_right)
+        let Bounds {
+            top_left,
+            bottom_right,
+        } = self.bounds();
+        iter_positions(&top_left, &bottom_right)
     }
 
+    /// the smallest rectangle containing every value and mark
     pub fn bounds(&self) -> Bounds {
-        Bounds {
-            top_left: self.top_left,
-            bottom_right: self.bottom_right,
+        if self.stale {
+            self.fit_bounds()
+        } else {
+            Bounds {
+                top_left: self.top_left,
+                bottom_right: self.bottom_right,
+            }
         }
     }
 
     pub fn bounds_size(&self) -> Size {
-        let width = modulus(self.bottom_right.x - self.top_left.x) + 1; // counting is hard!
-        let height = modulus(self.bottom_right.y - self.top_left.y) + 1;
+        let Bounds {
+            top_left,
+            bottom_right,
+        } = self.bounds();
+        let width = modulus(bottom_right.x - top_left.x) + 1; // counting is hard!
+        let height = modulus(bottom_right.y - top_left.y) + 1;
         Size { width, height }
     }
 
     /// the offset is the top_left of the grid
     pub fn offset(&self) -> Vector {
-        self.top_left
+        self.bounds().top_left
     }
 
-    pub fn count_visited(&self) -> usize {
-        self.cells.values().filter(|cell| cell.visited).count()
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.cells.values()
     }
 
-    pub fn cells(&self) -> impl Iterator<Item = &GridCell<T>> {
-        self.cells.values()
+    /// iter every value along with its position, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (Vector, &T)> {
+        self.cells
+            .iter()
+            .map(|(&position, value)| (position, value))
+    }
+
+    /// the number of values, not counting positions that are only marked
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// whether there are no values or marks
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty() && self.layers.is_empty()
+    }
+}
+
+impl<T, L: Copy + Eq + Hash> InfGrid<T, L> {
+    /// mark `position` in `layer`, returning whether it wasn't already
+    pub fn mark(&mut self, layer: L, position: Vector) -> bool {
+        self.extend_bounds(position);
+        self.layers.entry(layer).or_default().insert(position)
+    }
+
+    /// remove the mark at `position` in `layer`, returning whether there was one
+    pub fn unmark(&mut self, layer: L, position: Vector) -> bool {
+        let Some(marked) = self.layers.get_mut(&layer) else {
+            return false;
+        };
+        if !marked.remove(&position) {
+            return false;
+        }
+        if marked.is_empty() {
+            self.layers.remove(&layer);
+        }
+        self.shrink_bounds(position);
+        true
+    }
+
+    pub fn is_marked(&self, layer: L, position: Vector) -> bool {
+        self.layers
+            .get(&layer)
+            .is_some_and(|marked| marked.contains(&position))
+    }
+
+    /// iter the positions marked in `layer`, in no particular order
+    pub fn marked(&self, layer: L) -> impl Iterator<Item = Vector> + '_ {
+        self.layers.get(&layer).into_iter().flatten().copied()
+    }
+
+    /// the number of positions marked in `layer`
+    pub fn count_marked(&self, layer: L) -> usize {
+        self.layers.get(&layer).map_or(0, HashSet::len)
+    }
+}
+
+/// A position in an [`InfGrid`] that may or may not have a value yet
+///
+/// This struct is returned by the entry method of [`InfGrid`]
+pub struct Entry<'a, T, L = Visited> {
+    grid: &'a mut InfGrid<T, L>,
+    position: Vector,
+}
+
+impl<'a, T, L> Entry<'a, T, L> {
+    pub fn position(&self) -> Vector {
+        self.position
+    }
+
+    /// change the value if there is one
+    pub fn and_modify(self, f: impl FnOnce(&mut T)) -> Self {
+        if let Some(value) = self.grid.get_mut(self.position) {
+            f(value);
+        }
+        self
+    }
+
+    pub fn or_insert(self, value: T) -> &'a mut T {
+        self.or_insert_with(|| value)
+    }
+
+    /// add the value made by `f` if there isn't one there already
+    pub fn or_insert_with(self, f: impl FnOnce() -> T) -> &'a mut T {
+        if self.grid.get(self.position).is_none() {
+            self.grid.insert(self.position, f());
+        }
+        self.grid.get_mut(self.position).expect("just inserted")
     }
 }
 
-impl<T: Clone> InfGrid<T> {
-    pub fn visited(&self) -> impl Iterator<Item = GridCell<T>> + '_ {
-        self.cells.values().filter(|cell| cell.visited).cloned()
+impl<'a, T: Default, L> Entry<'a, T, L> {
+    pub fn or_default(self) -> &'a mut T {
+        self.or_insert_with(T::default)
     }
 }
 
-impl<T> Default for InfGrid<T> {
+impl<T, L> Default for InfGrid<T, L> {
     fn default() -> Self {
-        Self::new()
+        Self::with_layers()
     }
 }
 
-#[derive(Clone)]
+/// A rectangular grid, stored row by row in one contiguous `Vec`
+#[derive(Clone, PartialEq, Eq)]
 pub struct Grid<T> {
-    pub cells: Vec<Vec<T>>,
+    cells: Vec<T>,
+    width: usize,
+    height: usize,
+}
+
+/// Why the cells given to a [`Grid`] constructor don't make a rectangle
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GridError {
+    /// row `row` has `found` cells, but the rows before it had `expected`
+    RaggedRow {
+        row: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// there are `found` cells, but the width and height need `expected`
+    WrongSize { expected: usize, found: usize },
+}
+
+impl std::error::Error for GridError {}
+impl fmt::Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GridError::RaggedRow {
+                row,
+                expected,
+                found,
+            } => write!(f, "row {row} has {found} cells, expected {expected}"),
+            GridError::WrongSize { expected, found } => {
+                write!(f, "grid has {found} cells, expected {expected}")
+            }
+        }
+    }
+}
+
+/// Why puzzle text couldn't be parsed into a [`Grid`]
+///
+/// lines and columns count from 1, like a text editor
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ParseGridError {
+    /// line `line` has `found` characters, but the lines before it had `expected`
+    RaggedLine {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// `ch` isn't a valid cell
+    InvalidChar {
+        line: usize,
+        column: usize,
+        ch: char,
+    },
+}
+
+impl std::error::Error for ParseGridError {}
+impl fmt::Display for ParseGridError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseGridError::RaggedLine {
+                line,
+                expected,
+                found,
+            } => write!(f, "line {line} has {found} characters, expected {expected}"),
+            ParseGridError::InvalidChar { line, column, ch } => {
+                write!(
+                    f,
+                    "invalid character {ch:?} at line {line}, column {column}"
+                )
+            }
+        }
+    }
+}
+
+/// writes each row of the grid on its own line, with no separators between cells
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.rows() {
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
 }
 
 impl<T: fmt::Display> fmt::Debug for Grid<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for row in self.cells.iter() {
-            for height in row {
-                write!(f, "{}", height)?;
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// This struct is returned by the display_with method of [`Grid`]
+pub struct DisplayWith<'a, T, F> {
+    grid: &'a Grid<T>,
+    to_char: F,
+}
+
+impl<T, F: Fn(&T) -> char> fmt::Display for DisplayWith<'_, T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.grid.rows() {
+            for cell in row {
+                write!(f, "{}", (self.to_char)(cell))?;
             }
             writeln!(f)?;
         }
@@ -558,24 +1287,175 @@ impl<T: fmt::Display> fmt::Debug for Grid<T> {
 }
 
 impl<T> Grid<T> {
-    pub fn new(cells: Vec<Vec<T>>) -> Self {
-        Grid { cells }
+    /// parse one row per line of `input` and one cell per character, using `cell` to turn each character into a cell
+    ///
+    /// `cell` is given the coords the character is at and returns `None` if it isn't valid
+    pub fn from_str_with(
+        input: &str,
+        cell: impl FnMut(char, Coords) -> Option<T>,
+    ) -> Result<Self, ParseGridError> {
+        Self::from_str_with_markers(input, &[], cell).map(|(grid, _)| grid)
+    }
+
+    /// like [`Grid::from_str_with`], but also collects the coords of every character in `markers`, in reading order
+    ///
+    /// markers are still turned into cells by `cell`, so it decides what is underneath them
+    pub fn from_str_with_markers(
+        input: &str,
+        markers: &[char],
+        mut cell: impl FnMut(char, Coords) -> Option<T>,
+    ) -> Result<(Self, HashMap<char, Vec<Coords>>), ParseGridError> {
+        let mut cells = Vec::new();
+        let mut width = None;
+        let mut height = 0;
+        let mut found = HashMap::new();
+
+        for (y, line) in input.lines().enumerate() {
+            let row_start = cells.len();
+            for (x, ch) in line.chars().enumerate() {
+                let coords = Coords { x, y };
+                if markers.contains(&ch) {
+                    found.entry(ch).or_insert_with(Vec::new).push(coords);
+                }
+                cells.push(cell(ch, coords).ok_or(ParseGridError::InvalidChar {
+                    line: y + 1,
+                    column: x + 1,
+                    ch,
+                })?);
+            }
+            let row_width = cells.len() - row_start;
+            let expected = *width.get_or_insert(row_width);
+            if row_width != expected {
+                return Err(ParseGridError::RaggedLine {
+                    line: y + 1,
+                    expected,
+                    found: row_width,
+                });
+            }
+            height += 1;
+        }
+
+        let grid = Grid {
+            cells,
+            width: width.unwrap_or(0),
+            height,
+        };
+        Ok((grid, found))
+    }
+
+    /// display the grid with one character per cell, the reverse of [`Grid::from_str_with`]
+    pub fn display_with<F: Fn(&T) -> char>(&self, to_char: F) -> DisplayWith<'_, T, F> {
+        DisplayWith {
+            grid: self,
+            to_char,
+        }
+    }
+
+    /// build a grid from its rows, which must all be the same length
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Result<Self, GridError> {
+        let width = rows.first().map_or(0, Vec::len);
+        let height = rows.len();
+        let mut cells = Vec::with_capacity(width * height);
+        for (y, row) in rows.into_iter().enumerate() {
+            if row.len() != width {
+                return Err(GridError::RaggedRow {
+                    row: y,
+                    expected: width,
+                    found: row.len(),
+                });
+            }
+            cells.extend(row);
+        }
+        Ok(Grid {
+            cells,
+            width,
+            height,
+        })
+    }
+
+    /// build a grid from its cells, row by row
+    pub fn from_vec(width: usize, height: usize, cells: Vec<T>) -> Result<Self, GridError> {
+        if cells.len() != width * height {
+            return Err(GridError::WrongSize {
+                expected: width * height,
+                found: cells.len(),
+            });
+        }
+        Ok(Grid {
+            cells,
+            width,
+            height,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// where the cell at `index` is in `cells`, if it is in the grid
+    fn offset(&self, index: Coords) -> Option<usize> {
+        (index.x < self.width && index.y < self.height).then(|| index.y * self.width + index.x)
     }
 
     pub fn get(&self, index: Coords) -> Option<&T> {
-        self.cells.get(index.y).and_then(|row| row.get(index.x))
+        self.offset(index).and_then(|offset| self.cells.get(offset))
     }
 
     pub fn get_mut(&mut self, index: Coords) -> Option<&mut T> {
-        self.cells
-            .get_mut(index.y)
-            .and_then(|row| row.get_mut(index.x))
+        self.offset(index)
+            .and_then(|offset| self.cells.get_mut(offset))
     }
 
-    pub fn neighbours(&self, coords: Coords) -> NeighbourIter {
+    /// every cell, row by row
+    pub fn cells(&self) -> &[T] {
+        &self.cells
+    }
+
+    pub fn row(&self, y: usize) -> Option<&[T]> {
+        (y < self.height).then(|| &self.cells[y * self.width..(y + 1) * self.width])
+    }
+
+    pub fn row_mut(&mut self, y: usize) -> Option<&mut [T]> {
+        (y < self.height).then(|| &mut self.cells[y * self.width..(y + 1) * self.width])
+    }
+
+    /// iter the rows from top to bottom
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> + '_ {
+        (0..self.height).map(|y| &self.cells[y * self.width..(y + 1) * self.width])
+    }
+
+    /// iter the cells in column `x` from top to bottom
+    pub fn column(&self, x: usize) -> Option<impl Iterator<Item = &T> + '_> {
+        (x < self.width).then(|| self.cells[x..].iter().step_by(self.width))
+    }
+
+    /// the orthogonally adjacent coords that are in the grid
+    pub fn neighbours(&self, coords: Coords) -> NeighbourIter<'_> {
+        self.neighbours_at(coords, &VON_NEUMANN)
+    }
+
+    /// the adjacent coords that are in the grid, including diagonals
+    pub fn neighbours8(&self, coords: Coords) -> NeighbourIter<'_> {
+        self.neighbours_at(coords, &MOORE)
+    }
+
+    /// the coords in `neighbourhood` around `coords` that are in the grid
+    pub fn neighbours_in<'a>(
+        &'a self,
+        coords: Coords,
+        neighbourhood: &'a Neighbourhood,
+    ) -> NeighbourIter<'a> {
+        self.neighbours_at(coords, neighbourhood.offsets())
+    }
+
+    fn neighbours_at<'a>(&'a self, coords: Coords, offsets: &'a [Vector]) -> NeighbourIter<'a> {
         NeighbourIter {
-            inner: Box::new(directions_clockwise(Up).filter_map(move |dir| {
-                (coords + Vector::from(dir)).ok().and_then(|c| {
+            inner: Box::new(offsets.iter().filter_map(move |&offset| {
+                (coords + offset).ok().and_then(|c| {
                     // check these coords actually index into the grid
                     if self.get(c).is_some() {
                         Some(c)
@@ -589,8 +1469,8 @@ impl<T> Grid<T> {
 
     pub fn dimensions(&self) -> Size {
         Size {
-            width: self.cells[0].len(),
-            height: self.cells.len(),
+            width: self.width,
+            height: self.height,
         }
     }
 
@@ -600,6 +1480,31 @@ impl<T> Grid<T> {
     }
 }
 
+impl<T: Clone> Grid<T> {
+    /// a `width` by `height` grid with every cell set to `value`
+    pub fn filled(width: usize, height: usize, value: T) -> Self {
+        Grid {
+            cells: vec![value; width * height],
+            width,
+            height,
+        }
+    }
+}
+
+impl<T> Index<Coords> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, index: Coords) -> &Self::Output {
+        self.get(index).expect("coords inside the grid")
+    }
+}
+
+impl<T> IndexMut<Coords> for Grid<T> {
+    fn index_mut(&mut self, index: Coords) -> &mut Self::Output {
+        self.get_mut(index).expect("coords inside the grid")
+    }
+}
+
 /// This struct is returned by the neighbours method of [`Grid`]
 pub struct NeighbourIter<'a> {
     inner: Box<dyn Iterator<Item = Coords> + 'a>,
@@ -625,18 +1530,151 @@ impl FusedIterator for NeighbourIter<'_> {}
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashSet;
+
     use super::*;
 
+    #[test]
+    fn test_vector_simplify() {
+        assert_eq!(Vector::new(4, -6).simplify(), Vector::new(2, -3));
+        assert_eq!(Vector::new(-5, 0).simplify(), Vector::new(-1, 0));
+        assert_eq!(Vector::new(3, 7).simplify(), Vector::new(3, 7));
+        assert_eq!(
+            Vector::new(isize::MIN, isize::MIN).simplify(),
+            Vector::new(-1, -1)
+        );
+        assert_eq!(
+            Vector::new(isize::MIN, 2).simplify(),
+            Vector::new(isize::MIN / 2, 1)
+        );
+    }
+
+    fn diamonds() -> Vec<Diamond> {
+        vec![
+            Diamond::new(Vector::zero(), 0),
+            Diamond::new(Vector::new(1, -2), 1),
+            Diamond::new(Vector::new(-3, 2), 4),
+            Diamond::new(Vector::new(2, 3), 5),
+        ]
+    }
+
+    #[test]
+    fn test_diamond_slices() {
+        for diamond in diamonds() {
+            for i in -12..12 {
+                let row: Vec<isize> = (-12..12)
+                    .filter(|&x| diamond.contains(Vector::new(x, i)))
+                    .collect();
+                let column: Vec<isize> = (-12..12)
+                    .filter(|&y| diamond.contains(Vector::new(i, y)))
+                    .collect();
+                assert_eq!(diamond.row(i).unwrap_or_default().collect::<Vec<_>>(), row);
+                assert_eq!(
+                    diamond.column(i).unwrap_or_default().collect::<Vec<_>>(),
+                    column
+                );
+            }
+            let bounds = diamond.bounds();
+            assert_eq!(
+                iter_positions(&bounds.top_left, &bounds.bottom_right)
+                    .filter(|&position| diamond.contains(position))
+                    .count(),
+                2 * diamond.radius * (diamond.radius + 1) + 1
+            );
+        }
+    }
+
+    #[test]
+    fn test_diamond_boundary() {
+        assert_eq!(
+            Diamond::new(Vector::zero(), 1)
+                .boundary()
+                .collect::<Vec<_>>(),
+            vec![
+                Vector::new(0, -1),
+                Vector::new(1, 0),
+                Vector::new(0, 1),
+                Vector::new(-1, 0)
+            ]
+        );
+        for diamond in diamonds() {
+            let boundary: HashSet<Vector> = diamond.boundary().collect();
+            let expected: HashSet<Vector> =
+                iter_positions(&Vector::new(-12, -12), &Vector::new(12, 12))
+                    .filter(|&position| {
+                        diamond.center.manhattan_distance(position) == diamond.radius
+                    })
+                    .collect();
+            assert_eq!(boundary, expected);
+            assert_eq!(diamond.boundary().count(), expected.len());
+        }
+    }
+
+    #[test]
+    fn test_diamond_rotation() {
+        for diamond in diamonds() {
+            let square = diamond.to_rotated();
+            assert_eq!(Diamond::from_rotated(square), Some(diamond));
+            for position in iter_positions(&Vector::new(-12, -12), &Vector::new(12, 12)) {
+                let rotated = position.to_rotated();
+                assert_eq!(rotated.from_rotated(), Some(position));
+                let in_square = (square.top_left.x..=square.bottom_right.x).contains(&rotated.x)
+                    && (square.top_left.y..=square.bottom_right.y).contains(&rotated.y);
+                assert_eq!(diamond.contains(position), in_square);
+            }
+        }
+        assert_eq!(Vector::new(1, 0).from_rotated(), None);
+        // not square
+        assert_eq!(
+            Diamond::from_rotated(Bounds {
+                top_left: Vector::zero(),
+                bottom_right: Vector::new(2, 4)
+            }),
+            None
+        );
+    }
+
+    #[test]
+    fn test_diamond_intersection() {
+        for a in diamonds() {
+            for b in diamonds() {
+                let expected: HashSet<Vector> =
+                    iter_positions(&Vector::new(-12, -12), &Vector::new(12, 12))
+                        .filter(|&position| a.contains(position) && b.contains(position))
+                        .collect();
+                let found: HashSet<Vector> = a
+                    .intersection(&b)
+                    .map(|overlap| {
+                        iter_positions(&overlap.top_left, &overlap.bottom_right)
+                            .filter_map(Vector::from_rotated)
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                assert_eq!(found, expected, "{a:?} {b:?}");
+            }
+        }
+        // diamonds that only touch at a corner
+        let a = Diamond::new(Vector::zero(), 1);
+        let b = Diamond::new(Vector::new(2, 0), 1);
+        let corner = Vector::new(1, 0).to_rotated();
+        assert_eq!(
+            a.intersection(&b),
+            Some(Bounds {
+                top_left: corner,
+                bottom_right: corner
+            })
+        );
+        assert_eq!(a.intersection(&Diamond::new(Vector::new(3, 0), 1)), None);
+    }
+
     #[test]
     fn test_grid_positions_order() {
-        let grid: InfGrid<()> = InfGrid {
-            cells: HashMap::new(),
-            top_left: Vector { x: -2, y: -1 },
-            bottom_right: Vector { x: 0, y: 1 },
-        };
+        let mut grid = InfGrid::new();
+        grid.add(Vector { x: -2, y: -1 }, ());
+        grid.add(Vector { x: 0, y: 1 }, ());
 
         let mut positions = grid.positions();
-        assert_eq!(positions.next().unwrap(), grid.top_left);
+        assert_eq!(positions.next().unwrap(), grid.bounds().top_left);
         assert_eq!(positions.next().unwrap(), Vector { x: -1, y: -1 });
         assert_eq!(positions.next().unwrap(), Vector { x: 0, y: -1 });
         assert_eq!(positions.next().unwrap(), Vector { x: -2, y: 0 });
@@ -649,11 +1687,7 @@ mod tests {
 
     #[test]
     fn test_grid_positions_when_empty() {
-        let grid: InfGrid<()> = InfGrid {
-            cells: HashMap::new(),
-            top_left: Vector::zero(),
-            bottom_right: Vector::zero(),
-        };
+        let grid: InfGrid<()> = InfGrid::new();
 
         let total = grid.positions().count();
         assert_eq!(total, 1);
@@ -661,16 +1695,132 @@ mod tests {
 
     #[test]
     fn test_grid_positions_when_square() {
-        let grid: InfGrid<()> = InfGrid {
-            cells: HashMap::new(),
-            top_left: Vector { x: -2, y: -1 },
-            bottom_right: Vector { x: 0, y: 1 },
-        };
+        let mut grid = InfGrid::new();
+        grid.add(Vector { x: -2, y: -1 }, ());
+        grid.add(Vector { x: 0, y: 1 }, ());
 
         let total = grid.positions().count();
         assert_eq!(total, 9);
     }
 
+    #[test]
+    fn test_grid_bounds_follow_contents() {
+        let mut grid = InfGrid::new_off_center(Coords::new(5, 5));
+        assert_eq!(grid.bounds().top_left, Vector::new(5, 5));
+
+        // the first cell replaces the origin, rather than stretching the bounds back to it
+        grid.add(Vector::new(10, 12), 'a');
+        assert_eq!(grid.bounds().top_left, Vector::new(10, 12));
+        grid.add(Vector::new(-3, 14), 'b');
+        grid.add(Vector::new(0, 0), 'c');
+        assert_eq!(
+            grid.bounds(),
+            Bounds {
+                top_left: Vector::new(-3, 0),
+                bottom_right: Vector::new(10, 14)
+            }
+        );
+
+        // removing a cell from the edge shrinks the bounds, removing one from the middle doesn't
+        assert_eq!(grid.remove(Vector::new(-3, 14)), Some('b'));
+        assert_eq!(
+            grid.bounds(),
+            Bounds {
+                top_left: Vector::new(0, 0),
+                bottom_right: Vector::new(10, 12)
+            }
+        );
+        grid.shrink_to_fit_bounds();
+        assert_eq!(grid.bounds_size(), Size::new(11, 13));
+        assert!(grid.remove(Vector::new(-3, 14)).is_none());
+
+        grid.remove(Vector::new(0, 0));
+        grid.remove(Vector::new(10, 12));
+        assert!(grid.is_empty());
+        assert_eq!(grid.bounds().top_left, Vector::new(5, 5));
+        assert_eq!(grid, InfGrid::new_off_center(Coords::new(5, 5)));
+    }
+
+    #[test]
+    fn test_grid_entry_and_iter() {
+        let mut grid = InfGrid::new();
+        grid.entry(Vector::new(-1, 2)).or_insert(1);
+        grid.entry(Vector::new(-1, 2))
+            .and_modify(|value| *value += 10)
+            .or_insert(5);
+        *grid.entry(Vector::new(3, -4)).or_default() += 7;
+        assert_eq!(
+            grid.bounds(),
+            Bounds {
+                top_left: Vector::new(-1, -4),
+                bottom_right: Vector::new(3, 2)
+            }
+        );
+
+        let mut cells: Vec<_> = grid
+            .iter()
+            .map(|(position, &value)| (position, value))
+            .collect();
+        cells.sort_by_key(|(position, _)| position.x);
+        assert_eq!(
+            cells,
+            vec![(Vector::new(-1, 2), 11), (Vector::new(3, -4), 7)]
+        );
+        assert_eq!(grid.len(), 2);
+    }
+
+    #[test]
+    fn test_grid_layers() {
+        #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+        enum Overlay {
+            Seen,
+            Path,
+            Unused,
+        }
+        use Overlay::*;
+
+        let mut grid = InfGrid::with_layers();
+        grid.add(Vector::new(0, 0), '#');
+        assert!(grid.mark(Seen, Vector::new(0, 0)));
+        assert!(grid.mark(Seen, Vector::new(2, 1)));
+        assert!(!grid.mark(Seen, Vector::new(2, 1)));
+        assert!(grid.mark(Path, Vector::new(1, 0)));
+
+        // marks are kept apart from the values and from each other
+        assert_eq!(grid.len(), 1);
+        assert_eq!(grid.count_marked(Seen), 2);
+        assert_eq!(grid.count_marked(Path), 1);
+        assert_eq!(grid.count_marked(Unused), 0);
+        assert!(grid.is_marked(Seen, Vector::new(2, 1)));
+        assert!(!grid.is_marked(Path, Vector::new(2, 1)));
+        assert_eq!(grid.get(Vector::new(2, 1)), None);
+        assert_eq!(format!("{grid:?}").lines().skip(1).join("\n"), "'#',.\n..,");
+
+        // marks count towards the bounds too
+        assert_eq!(grid.bounds_size(), Size::new(3, 2));
+        assert!(grid.unmark(Seen, Vector::new(2, 1)));
+        assert!(!grid.unmark(Seen, Vector::new(2, 1)));
+        assert_eq!(grid.bounds_size(), Size::new(2, 1));
+        assert_eq!(grid.marked(Seen).collect::<Vec<_>>(), vec![Vector::zero()]);
+
+        // removing a value leaves the marks where it was
+        grid.remove(Vector::new(0, 0));
+        assert!(!grid.is_empty());
+        assert!(grid.is_marked(Seen, Vector::new(0, 0)));
+        grid.unmark(Seen, Vector::new(0, 0));
+        grid.unmark(Path, Vector::new(1, 0));
+        assert!(grid.is_empty());
+        assert_eq!(grid, InfGrid::with_layers());
+
+        // without its own layers, a grid marks positions as visited
+        let mut grid = InfGrid::new();
+        grid.add(Vector::zero(), ());
+        grid.mark(Visited, Vector::new(0, 3));
+        assert_eq!(grid.count_marked(Visited), 1);
+        assert!(grid.is_marked_in_any(Vector::new(0, 3)));
+        assert!(!grid.is_marked_in_any(Vector::zero()));
+    }
+
     struct Foo;
     impl fmt::Debug for Foo {
         fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -682,9 +1832,9 @@ mod tests {
     fn test_grid_positions_debug() {
         let mut grid: InfGrid<Foo> = InfGrid::new();
 
-        grid.add(Vector { x: -2, y: -1 }, Foo, false);
-        grid.add(Vector { x: -1, y: 0 }, Foo, false);
-        grid.add(Vector { x: 0, y: 1 }, Foo, false);
+        grid.add(Vector { x: -2, y: -1 }, Foo);
+        grid.add(Vector { x: -1, y: 0 }, Foo);
+        grid.add(Vector { x: 0, y: 1 }, Foo);
 
         assert_eq!(
             format!("{grid:?}"),
@@ -698,4 +1848,262 @@ InfGrid { top_left: Vector { x: -2, y: -1 }, bottom_right: Vector { x: 0, y: 1 }
             )
         );
     }
+
+    #[test]
+    fn test_direction_parsing() {
+        for style in [
+            DirectionStyle::Letter,
+            DirectionStyle::Arrow,
+            DirectionStyle::Compass,
+            DirectionStyle::Name,
+        ] {
+            for direction in Direction::all() {
+                let text = direction.display(style).to_string();
+                assert_eq!(text.parse::<Direction>(), Ok(direction), "{text}");
+            }
+        }
+        assert_eq!(Direction::try_from('v'), Ok(Down));
+        assert_eq!(Direction::try_from('E'), Ok(Right));
+        assert!(Direction::try_from('x').is_err());
+        assert!("UD".parse::<Direction>().is_err());
+        assert!("".parse::<Direction>().is_err());
+        assert_eq!(Left.to_string(), "L");
+    }
+
+    #[test]
+    fn test_direction_turns() {
+        assert_eq!(
+            Direction::all().collect::<Vec<_>>(),
+            vec![Up, Right, Down, Left]
+        );
+        for direction in Direction::all() {
+            assert_eq!(direction.opposite().opposite(), direction);
+            assert_eq!(
+                Vector::from(direction.opposite()),
+                Vector::from(direction) * -1
+            );
+            assert_eq!(direction.turn(Turn::Around), direction.opposite());
+            assert_eq!(direction.turn(Turn::Straight), direction);
+            assert_eq!(direction.turn(Turn::Left).turn(Turn::Right), direction);
+        }
+        // a turtle turning right at every step walks round a square back to where it started
+        let (mut position, mut facing) = (Vector::zero(), Up);
+        for _ in 0..4 {
+            position = position + Vector::from(facing);
+            facing = facing.turn(Turn::Right);
+        }
+        assert_eq!((position, facing), (Vector::zero(), Up));
+        assert_eq!(Up.turn(Turn::Left), Left);
+    }
+
+    #[test]
+    fn test_direction8_turns() {
+        assert_eq!(Up.turn_clockwise_45(), Direction8::UpRight);
+        assert_eq!(Left.turn_anticlockwise_45(), Direction8::DownLeft);
+        for direction in Direction8::ALL {
+            assert_eq!(
+                direction.turn_clockwise_45().turn_anticlockwise_45(),
+                direction
+            );
+            assert_eq!(
+                direction.turn_clockwise_45().turn_clockwise_45(),
+                direction.turn_clockwise()
+            );
+            assert_eq!(direction.turn_clockwise().turn_anticlockwise(), direction);
+            // turning 90° never changes whether a direction is diagonal
+            assert_eq!(
+                direction.turn_clockwise().is_diagonal(),
+                direction.is_diagonal()
+            );
+            assert_eq!(
+                Direction8::towards(Vector::from(direction) * 3),
+                Some(direction)
+            );
+        }
+        for direction in directions_clockwise(Up) {
+            let direction8 = Direction8::from(direction);
+            assert_eq!(Vector::from(direction8), Vector::from(direction));
+            assert_eq!(direction8.orthogonal(), Some(direction));
+            assert_eq!(
+                direction8.turn_clockwise().orthogonal(),
+                Some(direction.turn_clockwise())
+            );
+        }
+        assert_eq!(Direction8::DownLeft.orthogonal(), None);
+        assert_eq!(
+            Direction8::towards(Vector::new(5, -2)),
+            Some(Direction8::UpRight)
+        );
+        assert_eq!(Direction8::towards(Vector::zero()), None);
+        assert_eq!(
+            directions8_anticlockwise(Direction8::Up).nth(1),
+            Some(Direction8::UpLeft)
+        );
+        assert!(directions8_clockwise(Direction8::Up).eq(Direction8::ALL));
+    }
+
+    #[test]
+    fn test_neighbourhoods() {
+        let grid = Grid::from_rows(vec![vec![0; 3]; 3]).unwrap();
+        let centre = Coords::new(1, 1);
+        assert_eq!(grid.neighbours(centre).count(), 4);
+        assert_eq!(grid.neighbours8(centre).count(), 8);
+        assert_eq!(
+            grid.neighbours8(Coords::zero()).collect::<Vec<_>>(),
+            vec![Coords::new(1, 0), Coords::new(1, 1), Coords::new(0, 1)]
+        );
+
+        let knight = Neighbourhood::Custom(vec![Vector::new(1, 2), Vector::new(2, -1)]);
+        assert_eq!(
+            grid.neighbours_in(Coords::zero(), &knight)
+                .collect::<Vec<_>>(),
+            vec![Coords::new(1, 2)]
+        );
+
+        let mut inf_grid = InfGrid::new();
+        for position in [Vector::new(-1, -1), Vector::new(0, -1), Vector::new(3, 3)] {
+            inf_grid.add(position, ());
+        }
+        assert_eq!(
+            inf_grid.neighbours(Vector::zero()).collect::<Vec<_>>(),
+            vec![Vector::new(0, -1)]
+        );
+        assert_eq!(
+            inf_grid.neighbours8(Vector::zero()).collect::<Vec<_>>(),
+            vec![Vector::new(0, -1), Vector::new(-1, -1)]
+        );
+        assert_eq!(
+            inf_grid
+                .neighbours_in(Vector::zero(), &Neighbourhood::VonNeumann)
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_grid_construction() {
+        let grid = Grid::from_rows(vec![vec![1, 2, 3], vec![4, 5, 6]]).unwrap();
+        assert_eq!(
+            grid.dimensions(),
+            Size {
+                width: 3,
+                height: 2
+            }
+        );
+        assert_eq!(grid, Grid::from_vec(3, 2, vec![1, 2, 3, 4, 5, 6]).unwrap());
+        assert_eq!(
+            Grid::from_rows(vec![vec![1, 2], vec![3, 4], vec![5]]),
+            Err(GridError::RaggedRow {
+                row: 2,
+                expected: 2,
+                found: 1
+            })
+        );
+        assert_eq!(
+            Grid::from_vec(2, 2, vec![1, 2, 3]),
+            Err(GridError::WrongSize {
+                expected: 4,
+                found: 3
+            })
+        );
+
+        // an empty grid has no cells rather than panicking
+        let empty: Grid<u8> = Grid::from_rows(vec![]).unwrap();
+        assert_eq!(
+            empty.dimensions(),
+            Size {
+                width: 0,
+                height: 0
+            }
+        );
+        assert_eq!(empty.get(Coords::zero()), None);
+        assert_eq!(empty.iter_coords().count(), 0);
+    }
+
+    #[test]
+    fn test_grid_rows_and_columns() {
+        let mut grid = Grid::from_rows(vec![vec![1, 2, 3], vec![4, 5, 6]]).unwrap();
+        assert_eq!(grid.row(1), Some(&[4, 5, 6][..]));
+        assert_eq!(grid.row(2), None);
+        assert_eq!(grid.column(1).unwrap().collect::<Vec<_>>(), vec![&2, &5]);
+        assert!(grid.column(3).is_none());
+        assert_eq!(
+            grid.rows().collect::<Vec<_>>(),
+            vec![&[1, 2, 3], &[4, 5, 6]]
+        );
+
+        // the cell past the end of a row is not the first cell of the next one
+        assert_eq!(grid.get(Coords { x: 3, y: 0 }), None);
+        assert_eq!(grid[Coords { x: 0, y: 1 }], 4);
+        grid[Coords { x: 2, y: 1 }] = 9;
+        grid.row_mut(0).unwrap()[0] = 7;
+        assert_eq!(grid.cells(), &[7, 2, 3, 4, 5, 9]);
+        assert_eq!(format!("{grid:?}"), "723\n459\n");
+    }
+
+    #[test]
+    fn test_grid_parsing() {
+        let input = "Sab\nxyE\n";
+        let (grid, markers) = Grid::from_str_with_markers(input, &['S', 'E'], |ch, _| match ch {
+            'S' => Some(b'a'),
+            'E' => Some(b'z'),
+            ch => ch.is_ascii_lowercase().then_some(ch as u8),
+        })
+        .unwrap();
+        assert_eq!(grid.cells(), b"aabxyz");
+        assert_eq!(markers[&'S'], vec![Coords { x: 0, y: 0 }]);
+        assert_eq!(markers[&'E'], vec![Coords { x: 2, y: 1 }]);
+
+        assert_eq!(
+            Grid::from_str_with("123\n45\n", |ch, _| ch.to_digit(10)),
+            Err(ParseGridError::RaggedLine {
+                line: 2,
+                expected: 3,
+                found: 2
+            })
+        );
+        assert_eq!(
+            Grid::from_str_with("123\n4x6\n", |ch, _| ch.to_digit(10)),
+            Err(ParseGridError::InvalidChar {
+                line: 2,
+                column: 2,
+                ch: 'x'
+            })
+        );
+        assert_eq!(
+            Grid::from_str_with("", |ch, _| Some(ch))
+                .unwrap()
+                .dimensions(),
+            Size {
+                width: 0,
+                height: 0
+            }
+        );
+    }
+
+    #[test]
+    fn test_grid_round_trip() {
+        let input = "30373\n25512\n65332\n";
+        let grid = Grid::from_str_with(input, |ch, _| ch.to_digit(10)).unwrap();
+        assert_eq!(grid.to_string(), input);
+
+        enum Tile {
+            Open,
+            Wall,
+        }
+        let input = "#.#\n..#\n";
+        let grid = Grid::from_str_with(input, |ch, _| match ch {
+            '.' => Some(Tile::Open),
+            '#' => Some(Tile::Wall),
+            _ => None,
+        })
+        .unwrap();
+        let text = grid
+            .display_with(|tile| match tile {
+                Tile::Open => '.',
+                Tile::Wall => '#',
+            })
+            .to_string();
+        assert_eq!(text, input);
+    }
 }
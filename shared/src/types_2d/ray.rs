@@ -0,0 +1,142 @@
+//! Walk across a [`Grid`] in a straight line from a starting cell to the edge
+
+use std::iter::FusedIterator;
+
+use super::{Coords, Grid, Vector};
+
+impl<T> Grid<T> {
+    /// iter each cell in a straight line from `from` to the edge of the grid, moving by `step` each time
+    ///
+    /// `from` itself isn't included. `step` can be a [`Direction`](super::Direction),
+    /// a [`Direction8`](super::Direction8), or any [`Vector`] to skip over cells.
+    /// A zero step doesn't go anywhere, so yields nothing.
+    pub fn ray(&self, from: Coords, step: impl Into<Vector>) -> Ray<'_, T> {
+        Ray {
+            grid: self,
+            position: from,
+            step: step.into(),
+        }
+    }
+}
+
+/// This struct is returned by the ray method of [`Grid`]
+pub struct Ray<'a, T> {
+    grid: &'a Grid<T>,
+    position: Coords,
+    step: Vector,
+}
+
+impl<'a, T> Ray<'a, T> {
+    /// stop after the first cell where `blocked` is true, like a line of sight that can see the thing blocking it
+    pub fn take_until_inclusive<P>(self, blocked: P) -> TakeUntilInclusive<Self, P>
+    where
+        P: FnMut(&(Coords, &'a T)) -> bool,
+    {
+        TakeUntilInclusive::new(self, blocked)
+    }
+}
+
+impl<'a, T> Iterator for Ray<'a, T> {
+    type Item = (Coords, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.step == Vector::zero() {
+            return None;
+        }
+        let next = (self.position + self.step).ok()?;
+        let cell = self.grid.get(next)?;
+        self.position = next;
+        Some((next, cell))
+    }
+}
+
+// once a ray leaves the grid it stays where it was, so it keeps returning None
+impl<T> FusedIterator for Ray<'_, T> {}
+
+/// Yields items up to and including the first one that matches a predicate
+///
+/// This struct is returned by the take_until_inclusive method of [`Ray`], but works on any iterator
+pub struct TakeUntilInclusive<I, P> {
+    inner: I,
+    predicate: P,
+    done: bool,
+}
+
+impl<I, P> TakeUntilInclusive<I, P> {
+    pub fn new(inner: I, predicate: P) -> Self {
+        TakeUntilInclusive {
+            inner,
+            predicate,
+            done: false,
+        }
+    }
+}
+
+impl<I, P> Iterator for TakeUntilInclusive<I, P>
+where
+    I: Iterator,
+    P: FnMut(&I::Item) -> bool,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let item = self.inner.next()?;
+        self.done = (self.predicate)(&item);
+        Some(item)
+    }
+}
+
+impl<I, P> FusedIterator for TakeUntilInclusive<I, P>
+where
+    I: FusedIterator,
+    P: FnMut(&I::Item) -> bool,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Direction, Direction8};
+    use super::*;
+
+    fn grid() -> Grid<char> {
+        Grid::from_str_with("abcd\nefgh\nijkl\n", |ch, _| Some(ch)).unwrap()
+    }
+
+    fn cells<'a>(ray: impl Iterator<Item = (Coords, &'a char)>) -> String {
+        ray.map(|(_, &ch)| ch).collect()
+    }
+
+    #[test]
+    fn test_ray() {
+        let grid = grid();
+        let from = Coords::new(1, 1);
+        assert_eq!(cells(grid.ray(from, Direction::Right)), "gh");
+        assert_eq!(cells(grid.ray(from, Direction::Up)), "b");
+        assert_eq!(cells(grid.ray(from, Direction::Left)), "e");
+        assert_eq!(cells(grid.ray(from, Direction8::DownRight)), "k");
+        assert_eq!(cells(grid.ray(Coords::zero(), Vector::new(2, 1))), "g");
+        assert_eq!(cells(grid.ray(Coords::zero(), Vector::new(3, 0))), "d");
+        assert_eq!(cells(grid.ray(Coords::zero(), Direction::Up)), "");
+        assert_eq!(cells(grid.ray(from, Vector::zero())), "");
+        assert_eq!(
+            grid.ray(Coords::zero(), Direction::Down)
+                .map(|(coords, _)| coords)
+                .collect::<Vec<_>>(),
+            vec![Coords::new(0, 1), Coords::new(0, 2)]
+        );
+    }
+
+    #[test]
+    fn test_take_until_inclusive() {
+        let grid = grid();
+        let ray = grid.ray(Coords::zero(), Direction::Right);
+        assert_eq!(cells(ray.take_until_inclusive(|(_, &ch)| ch >= 'c')), "bc");
+        let ray = grid.ray(Coords::zero(), Direction::Right);
+        assert_eq!(cells(ray.take_until_inclusive(|_| false)), "bcd");
+        let ray = grid.ray(Coords::zero(), Direction::Right);
+        assert_eq!(cells(ray.take_until_inclusive(|_| true)), "b");
+    }
+}
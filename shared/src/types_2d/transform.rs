@@ -0,0 +1,369 @@
+//! Rotate, flip and crop [`Grid`]s, or look at part of one through a [`GridView`] without copying it
+
+use std::{fmt, ops::Index};
+
+use super::{
+    iter_coords, visualisation::Drawable, Bounds, Coords, Grid, NeighbourIter, Size, Vector, MOORE,
+    VON_NEUMANN,
+};
+
+impl<T: Clone> Grid<T> {
+    /// a `width` by `height` grid where each cell is copied from this grid at `source(coords)`
+    fn remap(&self, width: usize, height: usize, source: impl Fn(Coords) -> Coords) -> Self {
+        let cells = (0..height)
+            .flat_map(|y| (0..width).map(move |x| Coords { x, y }))
+            .map(|coords| self[source(coords)].clone())
+            .collect();
+        Grid {
+            cells,
+            width,
+            height,
+        }
+    }
+
+    /// swap rows and columns, mirroring the grid along the diagonal from the top left
+    pub fn transpose(&self) -> Self {
+        self.remap(self.height, self.width, |Coords { x, y }| Coords {
+            x: y,
+            y: x,
+        })
+    }
+
+    pub fn rotate_clockwise(&self) -> Self {
+        let bottom = self.height.saturating_sub(1);
+        self.remap(self.height, self.width, |Coords { x, y }| Coords {
+            x: y,
+            y: bottom - x,
+        })
+    }
+
+    pub fn rotate_anticlockwise(&self) -> Self {
+        let right = self.width.saturating_sub(1);
+        self.remap(self.height, self.width, |Coords { x, y }| Coords {
+            x: right - y,
+            y: x,
+        })
+    }
+
+    pub fn rotate_180(&self) -> Self {
+        let (right, bottom) = (self.width.saturating_sub(1), self.height.saturating_sub(1));
+        self.remap(self.width, self.height, |Coords { x, y }| Coords {
+            x: right - x,
+            y: bottom - y,
+        })
+    }
+
+    /// mirror left to right
+    pub fn flip_horizontal(&self) -> Self {
+        let right = self.width.saturating_sub(1);
+        self.remap(self.width, self.height, |Coords { x, y }| Coords {
+            x: right - x,
+            y,
+        })
+    }
+
+    /// mirror top to bottom
+    pub fn flip_vertical(&self) -> Self {
+        let bottom = self.height.saturating_sub(1);
+        self.remap(self.width, self.height, |Coords { x, y }| Coords {
+            x,
+            y: bottom - y,
+        })
+    }
+
+    /// copy the part of the grid inside `bounds`, which may be partly or entirely outside the grid
+    pub fn crop(&self, bounds: Bounds) -> Self {
+        self.view().clamped(bounds).to_grid()
+    }
+}
+
+impl<T> Grid<T> {
+    /// look at the whole grid as a [`GridView`]
+    pub fn view(&self) -> GridView<'_, T> {
+        GridView {
+            grid: self,
+            top_left: Coords::zero(),
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    /// look at the `size` part of the grid starting at `top_left`, without copying it
+    ///
+    /// `None` unless the window fits inside the grid
+    pub fn window(&self, top_left: Coords, size: Size) -> Option<GridView<'_, T>> {
+        self.view().window(top_left, size)
+    }
+}
+
+/// A rectangular part of a [`Grid`], with its own coords starting from (0, 0) at its top left
+///
+/// This struct is returned by the view and window methods of [`Grid`]
+pub struct GridView<'a, T> {
+    grid: &'a Grid<T>,
+    /// where the view starts in the grid
+    top_left: Coords,
+    width: usize,
+    height: usize,
+}
+
+// derive would require T: Clone and T: Copy
+impl<T> Clone for GridView<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for GridView<'_, T> {}
+
+impl<'a, T> GridView<'a, T> {
+    /// look at the `size` part of this view starting at `top_left`
+    ///
+    /// `None` unless the window fits inside this view
+    pub fn window(&self, top_left: Coords, size: Size) -> Option<GridView<'a, T>> {
+        let fits_x = top_left.x.checked_add(size.width)? <= self.width;
+        let fits_y = top_left.y.checked_add(size.height)? <= self.height;
+        (fits_x && fits_y).then_some(GridView {
+            grid: self.grid,
+            top_left: Coords {
+                x: self.top_left.x + top_left.x,
+                y: self.top_left.y + top_left.y,
+            },
+            width: size.width,
+            height: size.height,
+        })
+    }
+
+    /// the part of this view inside `bounds`, which is empty if they don't overlap
+    fn clamped(&self, bounds: Bounds) -> GridView<'a, T> {
+        let clamp = |n: isize, limit: usize| n.clamp(0, limit as isize) as usize;
+        let left = clamp(bounds.top_left.x, self.width);
+        let top = clamp(bounds.top_left.y, self.height);
+        // the bottom right of bounds is inclusive
+        let right = clamp(bounds.bottom_right.x.saturating_add(1), self.width).max(left);
+        let bottom = clamp(bounds.bottom_right.y.saturating_add(1), self.height).max(top);
+        self.window(
+            Coords::new(left, top),
+            Size::new(right - left, bottom - top),
+        )
+        .expect("clamped inside the view")
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn dimensions(&self) -> Size {
+        Size {
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    /// where `index` in this view is in the underlying grid
+    pub fn to_grid_coords(&self, index: Coords) -> Option<Coords> {
+        (index.x < self.width && index.y < self.height).then(|| Coords {
+            x: self.top_left.x + index.x,
+            y: self.top_left.y + index.y,
+        })
+    }
+
+    pub fn get(&self, index: Coords) -> Option<&'a T> {
+        self.to_grid_coords(index)
+            .and_then(|coords| self.grid.get(coords))
+    }
+
+    pub fn row(&self, y: usize) -> Option<&'a [T]> {
+        if y >= self.height {
+            return None;
+        }
+        let row = self.grid.row(self.top_left.y + y)?;
+        Some(&row[self.top_left.x..self.top_left.x + self.width])
+    }
+
+    /// iter the rows from top to bottom
+    pub fn rows(&self) -> impl Iterator<Item = &'a [T]> + 'a {
+        let view = *self;
+        (0..self.height).filter_map(move |y| view.row(y))
+    }
+
+    /// iter the cells in column `x` from top to bottom
+    pub fn column(&self, x: usize) -> Option<impl Iterator<Item = &'a T> + 'a> {
+        let view = *self;
+        (x < self.width)
+            .then(move || (0..view.height).filter_map(move |y| view.get(Coords { x, y })))
+    }
+
+    /// the orthogonally adjacent coords that are in the view
+    pub fn neighbours(&self, coords: Coords) -> NeighbourIter<'a> {
+        self.neighbours_at(coords, &VON_NEUMANN)
+    }
+
+    /// the adjacent coords that are in the view, including diagonals
+    pub fn neighbours8(&self, coords: Coords) -> NeighbourIter<'a> {
+        self.neighbours_at(coords, &MOORE)
+    }
+
+    fn neighbours_at(&self, coords: Coords, offsets: &'a [Vector]) -> NeighbourIter<'a> {
+        let view = *self;
+        NeighbourIter::new(offsets.iter().filter_map(move |&offset| {
+            (coords + offset)
+                .ok()
+                .filter(|&c| view.to_grid_coords(c).is_some())
+        }))
+    }
+
+    /// iter every Coord from left to right and top to bottom
+    pub fn iter_coords(&self) -> impl Iterator<Item = Coords> {
+        iter_coords(&self.dimensions())
+    }
+}
+
+impl<T: Clone> GridView<'_, T> {
+    /// copy the cells in the view into a grid of their own
+    pub fn to_grid(&self) -> Grid<T> {
+        Grid {
+            cells: self.rows().flatten().cloned().collect(),
+            width: self.width,
+            height: self.height,
+        }
+    }
+}
+
+impl<T> Index<Coords> for GridView<'_, T> {
+    type Output = T;
+
+    fn index(&self, index: Coords) -> &Self::Output {
+        self.get(index).expect("coords inside the view")
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for GridView<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.rows() {
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: fmt::Display> fmt::Debug for GridView<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl<T> Drawable for GridView<'_, T> {
+    type Cell = T;
+
+    fn draw_bounds(&self) -> Bounds {
+        Bounds {
+            top_left: Vector::zero(),
+            bottom_right: Vector::new(self.width as isize - 1, self.height as isize - 1),
+        }
+    }
+
+    fn draw_cell(&self, position: Vector) -> Option<&Self::Cell> {
+        Coords::try_from(position)
+            .ok()
+            .and_then(|coords| self.get(coords))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Lcg;
+
+    fn random_grid(rng: &mut Lcg) -> Grid<u64> {
+        let width = rng.below(6) as usize + 1;
+        let height = rng.below(6) as usize + 1;
+        let cells = (0..width * height).map(|_| rng.below(100)).collect();
+        Grid::from_vec(width, height, cells).unwrap()
+    }
+
+    fn grid(rows: &str) -> Grid<char> {
+        Grid::from_str_with(rows, |ch, _| Some(ch)).unwrap()
+    }
+
+    #[test]
+    fn test_rotations() {
+        let abc = grid("abc\ndef\n");
+        assert_eq!(abc.rotate_clockwise(), grid("da\neb\nfc\n"));
+        assert_eq!(abc.rotate_anticlockwise(), grid("cf\nbe\nad\n"));
+        assert_eq!(abc.rotate_180(), grid("fed\ncba\n"));
+        assert_eq!(abc.transpose(), grid("ad\nbe\ncf\n"));
+        assert_eq!(abc.flip_horizontal(), grid("cba\nfed\n"));
+        assert_eq!(abc.flip_vertical(), grid("def\nabc\n"));
+    }
+
+    #[test]
+    fn test_transform_properties() {
+        let mut rng = Lcg::new(45);
+        for _ in 0..100 {
+            let grid = random_grid(&mut rng);
+            let rotated = (0..4).fold(grid.clone(), |grid, _| grid.rotate_clockwise());
+            assert_eq!(rotated, grid);
+            assert_eq!(grid.rotate_clockwise().rotate_anticlockwise(), grid);
+            assert_eq!(
+                grid.rotate_clockwise().rotate_clockwise(),
+                grid.rotate_180()
+            );
+            assert_eq!(grid.transpose().transpose(), grid);
+            assert_eq!(grid.transpose().flip_horizontal(), grid.rotate_clockwise());
+            assert_eq!(grid.flip_horizontal().flip_vertical(), grid.rotate_180());
+            assert_eq!(grid.flip_horizontal().flip_horizontal(), grid);
+            assert_eq!(
+                grid.rotate_clockwise().dimensions(),
+                grid.transpose().dimensions()
+            );
+        }
+    }
+
+    #[test]
+    fn test_window() {
+        let grid = grid("abcd\nefgh\nijkl\n");
+        let window = grid.window(Coords::new(1, 1), Size::new(2, 2)).unwrap();
+        assert_eq!(window.to_string(), "fg\njk\n");
+        assert_eq!(window[Coords::new(1, 0)], 'g');
+        assert_eq!(window.get(Coords::new(2, 0)), None);
+        assert_eq!(window.row(1), Some(&['j', 'k'][..]));
+        assert_eq!(
+            window.column(0).unwrap().collect::<String>(),
+            "fj".to_string()
+        );
+        assert_eq!(window.neighbours8(Coords::zero()).count(), 3);
+        assert_eq!(
+            window.to_grid(),
+            Grid::from_rows(vec![vec!['f', 'g'], vec!['j', 'k']]).unwrap()
+        );
+
+        // windows of windows are relative to the window they're in
+        let inner = window.window(Coords::new(1, 1), Size::new(1, 1)).unwrap();
+        assert_eq!(inner.to_string(), "k\n");
+        assert!(window.window(Coords::new(1, 1), Size::new(2, 1)).is_none());
+        assert!(grid.window(Coords::new(0, 0), Size::new(5, 1)).is_none());
+        assert_eq!(grid.view().to_grid(), grid);
+    }
+
+    #[test]
+    fn test_crop() {
+        let grid = grid("abcd\nefgh\nijkl\n");
+        let bounds = |x1, y1, x2, y2| Bounds {
+            top_left: Vector::new(x1, y1),
+            bottom_right: Vector::new(x2, y2),
+        };
+        assert_eq!(grid.crop(bounds(1, 0, 2, 1)).to_string(), "bc\nfg\n");
+        // bounds hanging off the edges are clipped to the grid
+        assert_eq!(grid.crop(bounds(-5, 1, 1, 9)).to_string(), "ef\nij\n");
+        assert_eq!(grid.crop(bounds(10, 10, 20, 20)).dimensions(), Size::zero());
+        assert_eq!(grid.crop(bounds(-3, -3, -1, -1)).dimensions(), Size::zero());
+    }
+}
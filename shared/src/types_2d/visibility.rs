@@ -0,0 +1,169 @@
+//! How far can be seen from every cell of a grid of heights, looking along its rows and columns
+
+use std::iter;
+
+use super::{Coords, Direction, Grid};
+
+/// Which cells of a grid of heights can be seen from outside it, and how far can be seen from each one
+///
+/// A cell blocks the view of anything behind it that is no taller than it is.
+pub struct Visibility {
+    /// whether each cell can be seen from outside the grid, from any direction
+    visible: Grid<bool>,
+    /// how many cells can be seen from each cell in each direction, indexed by [`direction_index`]
+    distances: Grid<[usize; 4]>,
+}
+
+fn direction_index(direction: Direction) -> usize {
+    match direction {
+        Direction::Up => 0,
+        Direction::Right => 1,
+        Direction::Down => 2,
+        Direction::Left => 3,
+    }
+}
+
+impl Visibility {
+    /// look along every row and column of `heights`, in time proportional to the number of cells
+    pub fn of<T: Ord>(heights: &Grid<T>) -> Self {
+        let (width, height) = (heights.width(), heights.height());
+        let mut visibility = Visibility {
+            visible: Grid::filled(width, height, false),
+            distances: Grid::filled(width, height, [0; 4]),
+        };
+
+        for direction in Direction::all() {
+            // start each line at the edge we're looking towards, so everything in view has been seen already
+            let starts: Vec<Coords> = match direction {
+                Direction::Up => (0..width).map(|x| Coords::new(x, 0)).collect(),
+                Direction::Down => (0..width)
+                    .map(|x| Coords::new(x, height.saturating_sub(1)))
+                    .collect(),
+                Direction::Left => (0..height).map(|y| Coords::new(0, y)).collect(),
+                Direction::Right => (0..height)
+                    .map(|y| Coords::new(width.saturating_sub(1), y))
+                    .collect(),
+            };
+            for start in starts
+                .into_iter()
+                .filter(|&start| heights.get(start).is_some())
+            {
+                let line = iter::once((start, &heights[start]))
+                    .chain(heights.ray(start, direction.opposite()));
+                visibility.look_along(line, direction);
+            }
+        }
+        visibility
+    }
+
+    /// work out how far each cell in `line` can see in `direction`, where `line` starts at the edge in that direction
+    ///
+    /// keeps a stack of the cells seen so far that nothing since has hidden, which are in decreasing height order,
+    /// so each cell is pushed and popped at most once
+    fn look_along<'a, T: Ord + 'a>(
+        &mut self,
+        line: impl Iterator<Item = (Coords, &'a T)>,
+        direction: Direction,
+    ) {
+        let mut unblocked: Vec<(usize, &T)> = Vec::new();
+        for (distance_from_edge, (coords, height)) in line.enumerate() {
+            while unblocked.last().is_some_and(|&(_, other)| other < height) {
+                unblocked.pop();
+            }
+            let distance = match unblocked.last() {
+                // the view is blocked by the nearest cell that is at least as tall
+                Some(&(blocker, _)) => distance_from_edge - blocker,
+                None => {
+                    self.visible[coords] = true;
+                    distance_from_edge
+                }
+            };
+            self.distances[coords][direction_index(direction)] = distance;
+            unblocked.push((distance_from_edge, height));
+        }
+    }
+
+    /// whether the cell at `coords` can be seen from outside the grid, looking along a row or column
+    pub fn is_visible(&self, coords: Coords) -> bool {
+        self.visible.get(coords).copied().unwrap_or(false)
+    }
+
+    /// the number of cells that can be seen from outside the grid
+    pub fn visible_count(&self) -> usize {
+        self.visible
+            .cells()
+            .iter()
+            .filter(|&&visible| visible)
+            .count()
+    }
+
+    /// whether each cell can be seen from outside the grid
+    pub fn mask(&self) -> &Grid<bool> {
+        &self.visible
+    }
+
+    /// how many cells can be seen looking from `coords` in `direction`, including the one blocking the view
+    pub fn viewing_distance(&self, coords: Coords, direction: Direction) -> usize {
+        self.distances[coords][direction_index(direction)]
+    }
+
+    /// the viewing distances in every direction from `coords` multiplied together
+    pub fn scenic_score(&self, coords: Coords) -> usize {
+        self.distances[coords].iter().product()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn heights(input: &str) -> Grid<u32> {
+        Grid::from_str_with(input, |ch, _| ch.to_digit(10)).unwrap()
+    }
+
+    #[test]
+    fn test_visibility() {
+        let trees = heights("30373\n25512\n65332\n33549\n35390\n");
+        let visibility = Visibility::of(&trees);
+        assert_eq!(visibility.visible_count(), 21);
+        let mask = visibility
+            .mask()
+            .display_with(|&visible| if visible { '#' } else { '.' })
+            .to_string();
+        assert_eq!(mask, "#####\n###.#\n##.##\n#.#.#\n#####\n");
+
+        let coords = Coords::new(2, 3);
+        assert_eq!(visibility.viewing_distance(coords, Direction::Up), 2);
+        assert_eq!(visibility.viewing_distance(coords, Direction::Left), 2);
+        assert_eq!(visibility.viewing_distance(coords, Direction::Down), 1);
+        assert_eq!(visibility.viewing_distance(coords, Direction::Right), 2);
+        assert_eq!(visibility.scenic_score(coords), 8);
+        // trees on the edge can't see anything in one direction
+        assert_eq!(visibility.scenic_score(Coords::new(0, 2)), 0);
+    }
+
+    #[test]
+    fn test_small_grids() {
+        let visibility = Visibility::of(&heights("5\n"));
+        assert!(visibility.is_visible(Coords::zero()));
+        assert_eq!(visibility.scenic_score(Coords::zero()), 0);
+
+        let visibility = Visibility::of(&heights("1221\n"));
+        assert_eq!(visibility.visible_count(), 4);
+        assert_eq!(
+            visibility.viewing_distance(Coords::new(1, 0), Direction::Right),
+            1
+        );
+        assert_eq!(
+            visibility.viewing_distance(Coords::new(2, 0), Direction::Right),
+            1
+        );
+        assert_eq!(
+            visibility.viewing_distance(Coords::new(2, 0), Direction::Left),
+            1
+        );
+
+        let visibility = Visibility::of(&heights(""));
+        assert_eq!(visibility.visible_count(), 0);
+    }
+}
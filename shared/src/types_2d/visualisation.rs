@@ -0,0 +1,512 @@
+//! Record frames of a grid while a simulation runs, then play them back in the terminal or dump them to files
+//!
+//! Solutions can create a [`Recorder`] with [`Recorder::from_env`] and record as they go,
+//! it does nothing unless the `AOC_VISUALISE` environment variable is set to one of:
+//!
+//! * `terminal` - play the frames back in the terminal, in colour
+//! * `text:<dir>` - write each frame to a numbered text file in `<dir>`
+//! * `ppm:<dir>` - write each frame to a numbered PPM image in `<dir>`
+
+use std::{
+    env, fmt, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    thread,
+    time::Duration,
+};
+
+use super::{Bounds, Coords, Grid, InfGrid, Vector};
+
+/// A colour for the terminal or an image
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    pub const BLACK: Rgb = Rgb(0, 0, 0);
+    pub const WHITE: Rgb = Rgb(255, 255, 255);
+}
+
+/// How to draw a single cell
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Style {
+    pub glyph: char,
+    pub colour: Option<Rgb>,
+}
+
+impl Style {
+    /// a glyph in the terminal's default colour
+    pub fn plain(glyph: char) -> Self {
+        Style {
+            glyph,
+            colour: None,
+        }
+    }
+
+    pub fn coloured(glyph: char, colour: Rgb) -> Self {
+        Style {
+            glyph,
+            colour: Some(colour),
+        }
+    }
+
+    /// the colour of the cell in an image, cells without a colour are white unless their glyph is blank
+    fn pixel(&self) -> Rgb {
+        match self.colour {
+            Some(colour) => colour,
+            None if self.glyph.is_whitespace() => Rgb::BLACK,
+            None => Rgb::WHITE,
+        }
+    }
+}
+
+/// Something laid out on a grid that can be drawn into a [`Frame`]
+pub trait Drawable {
+    type Cell;
+
+    /// the top left and bottom right positions to draw, inclusive
+    fn draw_bounds(&self) -> Bounds;
+
+    fn draw_cell(&self, position: Vector) -> Option<&Self::Cell>;
+
+    /// whether `position` is marked apart from whatever is in it, like a layer of an [`InfGrid`]
+    fn draw_marked(&self, _position: Vector) -> bool {
+        false
+    }
+}
+
+impl<T, L> Drawable for InfGrid<T, L> {
+    type Cell = T;
+
+    fn draw_bounds(&self) -> Bounds {
+        self.bounds()
+    }
+
+    fn draw_cell(&self, position: Vector) -> Option<&Self::Cell> {
+        self.get(position)
+    }
+
+    fn draw_marked(&self, position: Vector) -> bool {
+        self.is_marked_in_any(position)
+    }
+}
+
+impl<T> Drawable for Grid<T> {
+    type Cell = T;
+
+    fn draw_bounds(&self) -> Bounds {
+        let size = self.dimensions();
+        Bounds {
+            top_left: Vector::zero(),
+            bottom_right: Vector::new(size.width as isize - 1, size.height as isize - 1),
+        }
+    }
+
+    fn draw_cell(&self, position: Vector) -> Option<&Self::Cell> {
+        Coords::try_from(position)
+            .ok()
+            .and_then(|coords| self.get(coords))
+    }
+}
+
+/// A snapshot of a grid, with every cell already styled
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Frame {
+    pub top_left: Vector,
+    pub width: usize,
+    pub height: usize,
+    cells: Vec<Style>,
+}
+
+impl Frame {
+    /// draw every cell of `grid` using `style`, which is given `None` for positions with nothing in them,
+    /// and whether the position is marked
+    pub fn new<G, F>(grid: &G, mut style: F) -> Self
+    where
+        G: Drawable + ?Sized,
+        F: FnMut(Option<&G::Cell>, bool) -> Style,
+    {
+        let Bounds {
+            top_left,
+            bottom_right,
+        } = grid.draw_bounds();
+        let width = usize::try_from(bottom_right.x - top_left.x + 1).unwrap_or(0);
+        let height = usize::try_from(bottom_right.y - top_left.y + 1).unwrap_or(0);
+
+        let cells = (top_left.y..top_left.y + height as isize)
+            .flat_map(|y| (top_left.x..top_left.x + width as isize).map(move |x| Vector::new(x, y)))
+            .map(|position| style(grid.draw_cell(position), grid.draw_marked(position)))
+            .collect();
+
+        Frame {
+            top_left,
+            width,
+            height,
+            cells,
+        }
+    }
+
+    /// the style of the cell at `position`, using the same coordinates as the grid the frame was drawn from
+    pub fn get(&self, position: Vector) -> Option<Style> {
+        let x = usize::try_from(position.x - self.top_left.x).ok()?;
+        let y = usize::try_from(position.y - self.top_left.y).ok()?;
+        if x < self.width && y < self.height {
+            Some(self.cells[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    fn rows(&self) -> impl Iterator<Item = &[Style]> {
+        // an empty frame has no rows, rather than one row for every cell
+        self.cells.chunks(self.width.max(1))
+    }
+
+    /// write the frame with ANSI escape codes to colour each glyph
+    pub fn write_ansi<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        for row in self.rows() {
+            let mut current = None;
+            for style in row {
+                if style.colour != current {
+                    match style.colour {
+                        Some(Rgb(r, g, b)) => write!(out, "\x1b[38;2;{r};{g};{b}m")?,
+                        None => write!(out, "\x1b[0m")?,
+                    }
+                    current = style.colour;
+                }
+                write!(out, "{}", style.glyph)?;
+            }
+            if current.is_some() {
+                write!(out, "\x1b[0m")?;
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+
+    /// write the frame as a binary PPM image, with each cell drawn as a `scale` by `scale` square
+    pub fn write_ppm<W: Write>(&self, out: &mut W, scale: usize) -> io::Result<()> {
+        write!(
+            out,
+            "P6\n{} {}\n255\n",
+            self.width * scale,
+            self.height * scale
+        )?;
+        for row in self.rows() {
+            let line: Vec<u8> = row
+                .iter()
+                .flat_map(|style| {
+                    let Rgb(r, g, b) = style.pixel();
+                    [r, g, b].repeat(scale)
+                })
+                .collect();
+            for _ in 0..scale {
+                out.write_all(&line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// the glyphs of the frame, without any colour
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.rows() {
+            for style in row {
+                write!(f, "{}", style.glyph)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Frames recorded one after another
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Recording {
+    pub frames: Vec<Frame>,
+}
+
+/// only one recording should play in the terminal at a time, even when both parts are running at once
+static TERMINAL: Mutex<()> = Mutex::new(());
+
+impl Recording {
+    /// play the frames one after another, redrawing over the previous frame
+    pub fn play<W: Write>(&self, out: &mut W, delay: Duration) -> io::Result<()> {
+        let _terminal = TERMINAL
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        for frame in &self.frames {
+            // move the cursor to the top left and clear the screen
+            write!(out, "\x1b[H\x1b[J")?;
+            frame.write_ansi(out)?;
+            out.flush()?;
+            thread::sleep(delay);
+        }
+        Ok(())
+    }
+
+    /// write each frame to `<dir>/frame_0000.txt`, `<dir>/frame_0001.txt`, ...
+    pub fn write_text_frames(&self, dir: impl AsRef<Path>) -> io::Result<()> {
+        self.write_frames(dir.as_ref(), "txt", |frame, out| write!(out, "{frame}"))
+    }
+
+    /// write each frame to `<dir>/frame_0000.ppm`, `<dir>/frame_0001.ppm`, ...
+    pub fn write_ppm_frames(&self, dir: impl AsRef<Path>, scale: usize) -> io::Result<()> {
+        self.write_frames(dir.as_ref(), "ppm", |frame, out| {
+            frame.write_ppm(out, scale)
+        })
+    }
+
+    fn write_frames<F>(&self, dir: &Path, extension: &str, mut write: F) -> io::Result<()>
+    where
+        F: FnMut(&Frame, &mut io::BufWriter<fs::File>) -> io::Result<()>,
+    {
+        fs::create_dir_all(dir)?;
+        // pad the numbers so the files sort in order
+        let digits = self.frames.len().to_string().len().max(4);
+        for (i, frame) in self.frames.iter().enumerate() {
+            let path = dir.join(format!("frame_{i:0digits$}.{extension}"));
+            let mut out = io::BufWriter::new(fs::File::create(path)?);
+            write(frame, &mut out)?;
+            out.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// What to do with the frames once the simulation is finished
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Output {
+    Terminal { delay: Duration },
+    Text { dir: PathBuf },
+    Ppm { dir: PathBuf, scale: usize },
+}
+
+impl Output {
+    /// read the output from the `AOC_VISUALISE` environment variable, if it is set
+    pub fn from_env() -> Option<Self> {
+        env::var("AOC_VISUALISE")
+            .ok()
+            .and_then(|var| Output::parse(&var))
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s.split_once(':') {
+            None if s == "terminal" => Some(Output::Terminal {
+                delay: Duration::from_millis(50),
+            }),
+            Some(("text", dir)) => Some(Output::Text { dir: dir.into() }),
+            Some(("ppm", dir)) => Some(Output::Ppm {
+                dir: dir.into(),
+                scale: 4,
+            }),
+            _ => None,
+        }
+    }
+
+    /// the same output, but keeping the frames of `name` apart from any others
+    fn named(&self, name: &str) -> Self {
+        match self {
+            Output::Terminal { delay } => Output::Terminal { delay: *delay },
+            Output::Text { dir } => Output::Text {
+                dir: dir.join(name),
+            },
+            Output::Ppm { dir, scale } => Output::Ppm {
+                dir: dir.join(name),
+                scale: *scale,
+            },
+        }
+    }
+}
+
+/// Styles and records frames of a grid during a simulation
+///
+/// Recording is skipped entirely when there is no [`Output`], so it costs nothing to leave in a solution.
+pub struct Recorder<F> {
+    style: F,
+    output: Option<Output>,
+    every: usize,
+    calls: usize,
+    recording: Recording,
+}
+
+impl<F> Recorder<F> {
+    pub fn new(output: Option<Output>, style: F) -> Self {
+        Recorder {
+            style,
+            output,
+            every: 1,
+            calls: 0,
+            recording: Recording::default(),
+        }
+    }
+
+    /// a recorder for the output chosen by the `AOC_VISUALISE` environment variable
+    ///
+    /// `name` keeps the frames apart from other recordings written to the same directory, e.g. "day14-part1"
+    pub fn from_env(name: &str, style: F) -> Self {
+        Self::new(Output::from_env().map(|output| output.named(name)), style)
+    }
+
+    /// only keep every `n`th frame, to save memory on long simulations
+    pub fn every(mut self, n: usize) -> Self {
+        self.every = n.max(1);
+        self
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.output.is_some()
+    }
+
+    /// record a frame of `grid`, unless it is one of the frames being skipped
+    pub fn record<G>(&mut self, grid: &G)
+    where
+        G: Drawable + ?Sized,
+        F: FnMut(Option<&G::Cell>, bool) -> Style,
+    {
+        if self.output.is_none() {
+            return;
+        }
+        if self.calls.is_multiple_of(self.every) {
+            let frame = Frame::new(grid, &mut self.style);
+            self.recording.frames.push(frame);
+        }
+        self.calls += 1;
+    }
+
+    /// the frames recorded so far
+    pub fn recording(&self) -> &Recording {
+        &self.recording
+    }
+
+    /// record the final frame of `grid`, whether or not it would have been skipped, then send every frame to the output
+    pub fn finish<G>(mut self, grid: &G) -> io::Result<()>
+    where
+        G: Drawable + ?Sized,
+        F: FnMut(Option<&G::Cell>, bool) -> Style,
+    {
+        let Some(output) = self.output else {
+            return Ok(());
+        };
+        self.recording
+            .frames
+            .push(Frame::new(grid, &mut self.style));
+
+        match output {
+            Output::Terminal { delay } => self.recording.play(&mut io::stdout().lock(), delay),
+            Output::Text { dir } => self.recording.write_text_frames(dir),
+            Output::Ppm { dir, scale } => self.recording.write_ppm_frames(dir, scale),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types_2d::Visited;
+
+    fn style(cell: Option<&char>, marked: bool) -> Style {
+        match cell.copied() {
+            Some('#') => Style::coloured('#', Rgb(255, 0, 0)),
+            Some(c) => Style::plain(c),
+            None if marked => Style::plain(','),
+            None => Style::plain('.'),
+        }
+    }
+
+    fn inf_grid() -> InfGrid<char> {
+        let mut grid = InfGrid::new();
+        grid.add(Vector::new(-1, 0), '#');
+        grid.add(Vector::new(1, 1), 'o');
+        grid
+    }
+
+    #[test]
+    fn test_frame() {
+        let frame = Frame::new(&inf_grid(), style);
+        assert_eq!(frame.to_string(), "#..\n..o\n");
+        assert_eq!(
+            frame.get(Vector::new(-1, 0)),
+            Some(Style::coloured('#', Rgb(255, 0, 0)))
+        );
+        assert_eq!(frame.get(Vector::new(2, 0)), None);
+
+        // marks are drawn where there is nothing else, and can stretch the frame
+        let mut grid = inf_grid();
+        grid.mark(Visited, Vector::new(0, 1));
+        grid.mark(Visited, Vector::new(1, 1));
+        grid.mark(Visited, Vector::new(1, 2));
+        assert_eq!(Frame::new(&grid, style).to_string(), "#..\n.,o\n..,\n");
+
+        let grid = Grid::from_rows(vec![vec![1, 2], vec![3, 4]]).unwrap();
+        let frame = Frame::new(&grid, |cell: Option<&u8>, _| {
+            Style::plain(char::from(b'0' + cell.copied().unwrap_or(0)))
+        });
+        assert_eq!(frame.to_string(), "12\n34\n");
+    }
+
+    #[test]
+    fn test_ansi() {
+        let mut out = Vec::new();
+        Frame::new(&inf_grid(), style).write_ansi(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\x1b[38;2;255;0;0m#\x1b[0m..\n..o\n"
+        );
+    }
+
+    #[test]
+    fn test_ppm() {
+        let mut out = Vec::new();
+        Frame::new(&inf_grid(), |_: Option<&char>, _| Style::plain(' '))
+            .write_ppm(&mut out, 2)
+            .unwrap();
+        let header = b"P6\n6 4\n255\n";
+        assert_eq!(&out[..header.len()], header);
+        assert_eq!(out.len(), header.len() + 6 * 4 * 3);
+    }
+
+    #[test]
+    fn test_recorder() {
+        let mut grid = inf_grid();
+        let mut recorder = Recorder::new(
+            Some(Output::Terminal {
+                delay: Duration::ZERO,
+            }),
+            style,
+        )
+        .every(2);
+        for x in 2..7 {
+            grid.add(Vector::new(x, 0), '#');
+            recorder.record(&grid);
+        }
+        let widths: Vec<usize> = recorder
+            .recording()
+            .frames
+            .iter()
+            .map(|frame| frame.width)
+            .collect();
+        assert_eq!(widths, vec![4, 6, 8]);
+
+        let mut recorder = Recorder::new(None, style);
+        recorder.record(&grid);
+        assert!(!recorder.is_recording());
+        assert!(recorder.recording().frames.is_empty());
+    }
+
+    #[test]
+    fn test_output() {
+        assert_eq!(
+            Output::parse("text:frames").map(|output| output.named("day14")),
+            Some(Output::Text {
+                dir: PathBuf::from("frames/day14")
+            })
+        );
+        assert!(matches!(
+            Output::parse("terminal"),
+            Some(Output::Terminal { .. })
+        ));
+        assert_eq!(Output::parse("gif:frames"), None);
+    }
+}
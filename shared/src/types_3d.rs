@@ -0,0 +1,778 @@
+//! Vectors, voxel sets and grids in three dimensions, for cubes, droplets and falling bricks
+//!
+//! These mirror [`types_2d`](crate::types_2d): a signed [`Vector3`] for positions and offsets that can go anywhere,
+//! unsigned [`Coords3`] for indexing into a dense [`Grid3`], and a sparse [`VoxelSet`] for shapes of any size.
+
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt,
+    ops::{Add, AddAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub},
+    str::FromStr,
+};
+
+use crate::{arithmetic::gcd, ValueError};
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Vector3 {
+    pub x: isize,
+    pub y: isize,
+    pub z: isize,
+}
+
+impl Vector3 {
+    pub fn new(x: isize, y: isize, z: isize) -> Self {
+        Vector3 { x, y, z }
+    }
+
+    pub fn zero() -> Self {
+        Vector3 { x: 0, y: 0, z: 0 }
+    }
+
+    /// return the smallest vector that points in the same direction
+    pub fn simplify(self) -> Self {
+        let Vector3 { x, y, z } = self;
+        match isize::try_from(gcd(gcd(x, y), z.unsigned_abs())) {
+            Ok(0) => self,
+            Ok(factor) => Vector3::new(x / factor, y / factor, z / factor),
+            // the gcd only fails to fit in an isize when every component is 0 or isize::MIN
+            Err(_) => Vector3::new(x.signum(), y.signum(), z.signum()),
+        }
+    }
+
+    pub fn manhattan_distance(&self, other: Vector3) -> usize {
+        (other.x - self.x).unsigned_abs()
+            + (other.y - self.y).unsigned_abs()
+            + (other.z - self.z).unsigned_abs()
+    }
+
+    /// the 6 positions that share a face with this one
+    pub fn neighbours(self) -> impl Iterator<Item = Vector3> {
+        FACES.iter().map(move |&offset| self + offset)
+    }
+
+    /// the positions around this one in `neighbourhood`
+    pub fn neighbours_in(
+        self,
+        neighbourhood: &Neighbourhood3,
+    ) -> impl Iterator<Item = Vector3> + '_ {
+        neighbourhood
+            .offsets()
+            .iter()
+            .map(move |&offset| self + offset)
+    }
+}
+
+impl Add for Vector3 {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Vector3 {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+            z: self.z + rhs.z,
+        }
+    }
+}
+
+impl AddAssign for Vector3 {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Vector3 {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Vector3 {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+            z: self.z - rhs.z,
+        }
+    }
+}
+
+impl Neg for Vector3 {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Vector3::zero() - self
+    }
+}
+
+impl Mul<isize> for Vector3 {
+    type Output = Self;
+
+    fn mul(self, rhs: isize) -> Self::Output {
+        Vector3 {
+            x: self.x * rhs,
+            y: self.y * rhs,
+            z: self.z * rhs,
+        }
+    }
+}
+
+impl MulAssign<isize> for Vector3 {
+    fn mul_assign(&mut self, rhs: isize) {
+        *self = *self * rhs;
+    }
+}
+
+/// written as `x,y,z`, which is also how it is parsed
+impl fmt::Display for Vector3 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{},{}", self.x, self.y, self.z)
+    }
+}
+
+/// parse `x,y,z`, allowing spaces around each number
+impl FromStr for Vector3 {
+    type Err = ValueError<String>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(',').map(|part| part.trim().parse());
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(Ok(x)), Some(Ok(y)), Some(Ok(z)), None) => Ok(Vector3 { x, y, z }),
+            _ => Err(ValueError(s.to_string())),
+        }
+    }
+}
+
+impl From<Coords3> for Vector3 {
+    fn from(other: Coords3) -> Self {
+        Vector3 {
+            x: other.x as isize,
+            y: other.y as isize,
+            z: other.z as isize,
+        }
+    }
+}
+
+/// A position in a [`Grid3`], which can't be negative
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Coords3 {
+    pub x: usize,
+    pub y: usize,
+    pub z: usize,
+}
+
+impl Coords3 {
+    pub fn new(x: usize, y: usize, z: usize) -> Self {
+        Coords3 { x, y, z }
+    }
+
+    pub fn zero() -> Self {
+        Coords3 { x: 0, y: 0, z: 0 }
+    }
+
+    pub fn manhattan_distance(&self, other: Coords3) -> usize {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y) + self.z.abs_diff(other.z)
+    }
+}
+
+/// the position a [`Vector3`] would end up at if it was used as [`Coords3`], when one or more of x, y, z is negative
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct OutOfBounds3(pub Vector3);
+
+impl Add<Vector3> for Coords3 {
+    type Output = Result<Coords3, OutOfBounds3>;
+
+    fn add(self, rhs: Vector3) -> Self::Output {
+        let position = Vector3::from(self) + rhs;
+        match (
+            usize::try_from(position.x),
+            usize::try_from(position.y),
+            usize::try_from(position.z),
+        ) {
+            (Ok(x), Ok(y), Ok(z)) => Ok(Coords3 { x, y, z }),
+            _ => Err(OutOfBounds3(position)),
+        }
+    }
+}
+
+impl Sub for Coords3 {
+    type Output = Vector3;
+
+    fn sub(self, rhs: Coords3) -> Self::Output {
+        Vector3::from(self) - Vector3::from(rhs)
+    }
+}
+
+impl TryFrom<Vector3> for Coords3 {
+    type Error = OutOfBounds3;
+
+    fn try_from(value: Vector3) -> Result<Self, Self::Error> {
+        Coords3::zero() + value
+    }
+}
+
+/// every offset to an adjacent cube: the 6 that share a face, then the 12 that share only an edge,
+/// then the 8 that share only a corner
+const OFFSETS: [Vector3; 26] = {
+    const fn v(x: isize, y: isize, z: isize) -> Vector3 {
+        Vector3 { x, y, z }
+    }
+    [
+        // faces
+        v(1, 0, 0),
+        v(-1, 0, 0),
+        v(0, 1, 0),
+        v(0, -1, 0),
+        v(0, 0, 1),
+        v(0, 0, -1),
+        // edges
+        v(1, 1, 0),
+        v(1, -1, 0),
+        v(-1, 1, 0),
+        v(-1, -1, 0),
+        v(1, 0, 1),
+        v(1, 0, -1),
+        v(-1, 0, 1),
+        v(-1, 0, -1),
+        v(0, 1, 1),
+        v(0, 1, -1),
+        v(0, -1, 1),
+        v(0, -1, -1),
+        // corners
+        v(1, 1, 1),
+        v(1, 1, -1),
+        v(1, -1, 1),
+        v(1, -1, -1),
+        v(-1, 1, 1),
+        v(-1, 1, -1),
+        v(-1, -1, 1),
+        v(-1, -1, -1),
+    ]
+};
+
+const FACES: &[Vector3] = OFFSETS.split_at(6).0;
+const EDGES: &[Vector3] = OFFSETS.split_at(18).0;
+
+/// Which cubes count as adjacent to each other
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Neighbourhood3 {
+    /// the 6 cubes sharing a face
+    Faces,
+    /// the 18 cubes sharing a face or an edge
+    Edges,
+    /// all 26 cubes touching at all, even just at a corner
+    Corners,
+    /// any other offsets
+    Custom(Vec<Vector3>),
+}
+
+impl Neighbourhood3 {
+    pub fn offsets(&self) -> &[Vector3] {
+        match self {
+            Neighbourhood3::Faces => FACES,
+            Neighbourhood3::Edges => EDGES,
+            Neighbourhood3::Corners => &OFFSETS,
+            Neighbourhood3::Custom(offsets) => offsets,
+        }
+    }
+}
+
+/// A cuboid of positions, including both corners
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Bounds3 {
+    pub min: Vector3,
+    pub max: Vector3,
+}
+
+impl Bounds3 {
+    /// the smallest bounds containing every position, `None` if there aren't any
+    pub fn of(positions: impl IntoIterator<Item = Vector3>) -> Option<Self> {
+        let mut positions = positions.into_iter();
+        let first = positions.next()?;
+        Some(positions.fold(
+            Bounds3 {
+                min: first,
+                max: first,
+            },
+            |Bounds3 { min, max }, position| Bounds3 {
+                min: Vector3::new(
+                    min.x.min(position.x),
+                    min.y.min(position.y),
+                    min.z.min(position.z),
+                ),
+                max: Vector3::new(
+                    max.x.max(position.x),
+                    max.y.max(position.y),
+                    max.z.max(position.z),
+                ),
+            },
+        ))
+    }
+
+    pub fn contains(&self, position: Vector3) -> bool {
+        (self.min.x..=self.max.x).contains(&position.x)
+            && (self.min.y..=self.max.y).contains(&position.y)
+            && (self.min.z..=self.max.z).contains(&position.z)
+    }
+
+    /// grow the bounds by `by` in every direction
+    pub fn expand(self, by: isize) -> Self {
+        let by = Vector3::new(by, by, by);
+        Bounds3 {
+            min: self.min - by,
+            max: self.max + by,
+        }
+    }
+
+    /// iter every position in the bounds, x first, then y, then z
+    pub fn positions(&self) -> impl Iterator<Item = Vector3> {
+        let Bounds3 { min, max } = *self;
+        (min.z..=max.z).flat_map(move |z| {
+            (min.y..=max.y).flat_map(move |y| (min.x..=max.x).map(move |x| Vector3::new(x, y, z)))
+        })
+    }
+}
+
+/// A sparse set of unit cubes, each one named by the position of its corner nearest negative infinity
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct VoxelSet {
+    voxels: HashSet<Vector3>,
+}
+
+impl VoxelSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// add a cube, returning whether it wasn't already there
+    pub fn insert(&mut self, position: Vector3) -> bool {
+        self.voxels.insert(position)
+    }
+
+    /// take a cube out, returning whether it was there
+    pub fn remove(&mut self, position: Vector3) -> bool {
+        self.voxels.remove(&position)
+    }
+
+    pub fn contains(&self, position: Vector3) -> bool {
+        self.voxels.contains(&position)
+    }
+
+    pub fn len(&self) -> usize {
+        self.voxels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.voxels.is_empty()
+    }
+
+    /// iter every cube, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = Vector3> + '_ {
+        self.voxels.iter().copied()
+    }
+
+    /// the smallest bounds containing every cube, `None` if there aren't any
+    pub fn bounds(&self) -> Option<Bounds3> {
+        Bounds3::of(self.iter())
+    }
+
+    /// the number of faces not touching another cube, including ones facing pockets trapped inside
+    pub fn surface_area(&self) -> usize {
+        self.iter()
+            .flat_map(Vector3::neighbours)
+            .filter(|&neighbour| !self.contains(neighbour))
+            .count()
+    }
+
+    /// every empty position reachable from outside without passing through a cube, within one of the bounds
+    ///
+    /// the surrounding layer means the outside is all one region, however the cubes are arranged
+    pub fn exterior(&self) -> VoxelSet {
+        let Some(bounds) = self.bounds().map(|bounds| bounds.expand(1)) else {
+            return VoxelSet::new();
+        };
+        let mut outside = VoxelSet::new();
+        outside.insert(bounds.min);
+        let mut queue = VecDeque::from([bounds.min]);
+        while let Some(position) = queue.pop_front() {
+            for neighbour in position.neighbours() {
+                if bounds.contains(neighbour)
+                    && !self.contains(neighbour)
+                    && outside.insert(neighbour)
+                {
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+        outside
+    }
+
+    /// the number of faces that can be reached from outside, leaving out any facing pockets trapped inside
+    pub fn exterior_surface_area(&self) -> usize {
+        let outside = self.exterior();
+        self.iter()
+            .flat_map(Vector3::neighbours)
+            .filter(|&neighbour| outside.contains(neighbour))
+            .count()
+    }
+
+    /// these cubes with every pocket trapped inside them filled in
+    pub fn fill_interior(&self) -> VoxelSet {
+        let Some(bounds) = self.bounds() else {
+            return VoxelSet::new();
+        };
+        let outside = self.exterior();
+        bounds
+            .positions()
+            .filter(|&position| !outside.contains(position))
+            .collect()
+    }
+}
+
+impl FromIterator<Vector3> for VoxelSet {
+    fn from_iter<I: IntoIterator<Item = Vector3>>(iter: I) -> Self {
+        VoxelSet {
+            voxels: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Extend<Vector3> for VoxelSet {
+    fn extend<I: IntoIterator<Item = Vector3>>(&mut self, iter: I) {
+        self.voxels.extend(iter);
+    }
+}
+
+/// A cuboid grid, stored layer by layer in one contiguous `Vec`
+///
+/// each layer is a fixed `z`, stored row by row like a [`Grid`](crate::types_2d::Grid)
+#[derive(Clone, Eq, PartialEq)]
+pub struct Grid3<T> {
+    cells: Vec<T>,
+    width: usize,
+    height: usize,
+    depth: usize,
+}
+
+impl<T: fmt::Debug> fmt::Debug for Grid3<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Grid3")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("depth", &self.depth)
+            .field("cells", &self.cells)
+            .finish()
+    }
+}
+
+impl<T: Clone> Grid3<T> {
+    /// a `width` by `height` by `depth` grid with every cell set to `value`
+    pub fn filled(width: usize, height: usize, depth: usize, value: T) -> Self {
+        Grid3 {
+            cells: vec![value; width * height * depth],
+            width,
+            height,
+            depth,
+        }
+    }
+}
+
+impl<T> Grid3<T> {
+    /// build a grid by calling `cell` for every position, x first, then y, then z
+    pub fn from_fn(
+        width: usize,
+        height: usize,
+        depth: usize,
+        mut cell: impl FnMut(Coords3) -> T,
+    ) -> Self {
+        let cells = iter_coords3(width, height, depth).map(&mut cell).collect();
+        Grid3 {
+            cells,
+            width,
+            height,
+            depth,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    fn offset(&self, coords: Coords3) -> Option<usize> {
+        (coords.x < self.width && coords.y < self.height && coords.z < self.depth)
+            .then(|| (coords.z * self.height + coords.y) * self.width + coords.x)
+    }
+
+    pub fn get(&self, coords: Coords3) -> Option<&T> {
+        self.offset(coords)
+            .and_then(|offset| self.cells.get(offset))
+    }
+
+    pub fn get_mut(&mut self, coords: Coords3) -> Option<&mut T> {
+        self.offset(coords)
+            .and_then(|offset| self.cells.get_mut(offset))
+    }
+
+    /// every cell, x first, then y, then z
+    pub fn cells(&self) -> &[T] {
+        &self.cells
+    }
+
+    /// iter every position in the grid, in the same order as [`Grid3::cells`]
+    pub fn iter_coords(&self) -> impl Iterator<Item = Coords3> {
+        iter_coords3(self.width, self.height, self.depth)
+    }
+
+    /// the positions in the grid that share a face with `coords`
+    pub fn neighbours(&self, coords: Coords3) -> impl Iterator<Item = Coords3> + '_ {
+        self.neighbours_at(coords, FACES)
+    }
+
+    /// the positions in the grid around `coords` in `neighbourhood`
+    pub fn neighbours_in<'a>(
+        &'a self,
+        coords: Coords3,
+        neighbourhood: &'a Neighbourhood3,
+    ) -> impl Iterator<Item = Coords3> + 'a {
+        self.neighbours_at(coords, neighbourhood.offsets())
+    }
+
+    fn neighbours_at<'a>(
+        &'a self,
+        coords: Coords3,
+        offsets: &'a [Vector3],
+    ) -> impl Iterator<Item = Coords3> + 'a {
+        offsets
+            .iter()
+            .filter_map(move |&offset| (coords + offset).ok())
+            .filter(|&neighbour| self.offset(neighbour).is_some())
+    }
+
+    /// every cell that can be reached from `start` through cells sharing a face, where `can_enter` is true
+    ///
+    /// `start` is only included if `can_enter` is true for it too
+    pub fn flood_fill(
+        &self,
+        start: Coords3,
+        mut can_enter: impl FnMut(Coords3, &T) -> bool,
+    ) -> Grid3<bool> {
+        let mut reached = Grid3::filled(self.width, self.height, self.depth, false);
+        let mut queue = VecDeque::new();
+        if self.get(start).is_some_and(|cell| can_enter(start, cell)) {
+            reached[start] = true;
+            queue.push_back(start);
+        }
+        while let Some(coords) = queue.pop_front() {
+            for neighbour in self.neighbours(coords) {
+                if !reached[neighbour] && can_enter(neighbour, &self[neighbour]) {
+                    reached[neighbour] = true;
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+        reached
+    }
+}
+
+impl Grid3<bool> {
+    /// the number of faces of the `true` cells that don't touch another `true` cell, including faces on the edge
+    pub fn surface_area(&self) -> usize {
+        self.iter_coords()
+            .filter(|&coords| self[coords])
+            .map(|coords| 6 - self.neighbours(coords).filter(|&n| self[n]).count())
+            .sum()
+    }
+
+    /// the `true` cells as voxels, `offset` being where the grid's origin goes
+    pub fn to_voxels(&self, offset: Vector3) -> VoxelSet {
+        self.iter_coords()
+            .filter(|&coords| self[coords])
+            .map(|coords| offset + Vector3::from(coords))
+            .collect()
+    }
+}
+
+impl<T> Index<Coords3> for Grid3<T> {
+    type Output = T;
+
+    fn index(&self, coords: Coords3) -> &Self::Output {
+        self.get(coords).expect("coords inside the grid")
+    }
+}
+
+impl<T> IndexMut<Coords3> for Grid3<T> {
+    fn index_mut(&mut self, coords: Coords3) -> &mut Self::Output {
+        self.get_mut(coords).expect("coords inside the grid")
+    }
+}
+
+/// iter every Coords3 in a cuboid, x first, then y, then z
+pub fn iter_coords3(width: usize, height: usize, depth: usize) -> impl Iterator<Item = Coords3> {
+    (0..depth).flat_map(move |z| {
+        (0..height).flat_map(move |y| (0..width).map(move |x| Coords3 { x, y, z }))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// the example droplet from 2022 day 18
+    const DROPLET: &str = "\
+2,2,2
+1,2,2
+3,2,2
+2,1,2
+2,3,2
+2,2,1
+2,2,3
+2,2,4
+2,2,6
+1,2,5
+3,2,5
+2,1,5
+2,3,5";
+
+    fn droplet() -> VoxelSet {
+        DROPLET.lines().map(|line| line.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_vector3() {
+        let a = Vector3::new(1, -2, 3);
+        let b = Vector3::new(4, 0, -1);
+        assert_eq!(a + b, Vector3::new(5, -2, 2));
+        assert_eq!(a - b, Vector3::new(-3, -2, 4));
+        assert_eq!(-a, Vector3::new(-1, 2, -3));
+        assert_eq!(a * 3, Vector3::new(3, -6, 9));
+        assert_eq!(a.manhattan_distance(b), 9);
+        assert_eq!(Vector3::new(4, -6, 10).simplify(), Vector3::new(2, -3, 5));
+        assert_eq!(Vector3::new(0, 0, -7).simplify(), Vector3::new(0, 0, -1));
+        assert_eq!(Vector3::zero().simplify(), Vector3::zero());
+
+        assert_eq!(" 1, -2,3".parse(), Ok(a));
+        assert_eq!(a.to_string().parse(), Ok(a));
+        assert!("1,2".parse::<Vector3>().is_err());
+        assert!("1,2,3,4".parse::<Vector3>().is_err());
+        assert!("1,b,3".parse::<Vector3>().is_err());
+    }
+
+    #[test]
+    fn test_coords3() {
+        let coords = Coords3::new(1, 2, 3);
+        assert_eq!(coords + Vector3::new(-1, 0, 1), Ok(Coords3::new(0, 2, 4)));
+        assert_eq!(
+            coords + Vector3::new(0, -3, 0),
+            Err(OutOfBounds3(Vector3::new(1, -1, 3)))
+        );
+        assert_eq!(Coords3::try_from(Vector3::from(coords)), Ok(coords));
+        assert_eq!(Coords3::new(4, 2, 0) - coords, Vector3::new(3, 0, -3));
+        assert_eq!(coords.manhattan_distance(Coords3::zero()), 6);
+    }
+
+    #[test]
+    fn test_neighbourhoods() {
+        for (neighbourhood, count, max_distance) in [
+            (Neighbourhood3::Faces, 6, 1),
+            (Neighbourhood3::Edges, 18, 2),
+            (Neighbourhood3::Corners, 26, 3),
+        ] {
+            let offsets: HashSet<Vector3> = neighbourhood.offsets().iter().copied().collect();
+            assert_eq!(offsets.len(), count);
+            assert!(!offsets.contains(&Vector3::zero()));
+            assert!(offsets.iter().all(|offset| {
+                offset.manhattan_distance(Vector3::zero()) <= max_distance
+                    && [offset.x, offset.y, offset.z].iter().all(|n| n.abs() <= 1)
+            }));
+        }
+        let knight = Neighbourhood3::Custom(vec![Vector3::new(1, 2, 0)]);
+        assert_eq!(
+            Vector3::zero().neighbours_in(&knight).collect::<Vec<_>>(),
+            vec![Vector3::new(1, 2, 0)]
+        );
+    }
+
+    #[test]
+    fn test_surface_area() {
+        let droplet = droplet();
+        assert_eq!(droplet.surface_area(), 64);
+        assert_eq!(droplet.exterior_surface_area(), 58);
+
+        // the only pocket is the single cube at 2,2,5
+        let filled = droplet.fill_interior();
+        assert_eq!(filled.len(), droplet.len() + 1);
+        assert!(filled.contains(Vector3::new(2, 2, 5)));
+        assert_eq!(filled.surface_area(), 58);
+
+        let pair: VoxelSet = [Vector3::zero(), Vector3::new(1, 0, 0)]
+            .into_iter()
+            .collect();
+        assert_eq!(pair.surface_area(), 10);
+        assert_eq!(pair.exterior_surface_area(), 10);
+        assert_eq!(VoxelSet::new().exterior_surface_area(), 0);
+    }
+
+    #[test]
+    fn test_grid3() {
+        let mut grid = Grid3::from_fn(2, 3, 4, |Coords3 { x, y, z }| x + 10 * y + 100 * z);
+        assert_eq!(grid[Coords3::new(1, 2, 3)], 321);
+        assert_eq!(grid.get(Coords3::new(2, 0, 0)), None);
+        assert_eq!(grid.cells().len(), 24);
+        assert_eq!(grid.cells()[..3], [0, 1, 10]);
+        grid[Coords3::zero()] = 7;
+        assert_eq!(grid.get(Coords3::zero()), Some(&7));
+
+        assert_eq!(grid.neighbours(Coords3::zero()).count(), 3);
+        assert_eq!(grid.neighbours(Coords3::new(1, 1, 1)).count(), 5);
+        assert_eq!(
+            grid.neighbours_in(Coords3::zero(), &Neighbourhood3::Corners)
+                .count(),
+            7
+        );
+        assert_eq!(
+            grid.iter_coords()
+                .map(|coords| grid[coords])
+                .collect::<Vec<_>>(),
+            grid.cells()
+        );
+    }
+
+    #[test]
+    fn test_flood_fill() {
+        // a hollow 3x3x3 cube with a solid wall through the middle of the 5x5x5 grid around it
+        let solid = Grid3::from_fn(5, 5, 5, |Coords3 { x, y, z }| {
+            let shell =
+                [x, y, z].iter().all(|n| (1..=3).contains(n)) && [x, y, z].iter().any(|&n| n != 2);
+            shell || x == 4
+        });
+        let outside = solid.flood_fill(Coords3::zero(), |_, &solid| !solid);
+        assert!(outside[Coords3::zero()]);
+        assert!(outside[Coords3::new(0, 4, 4)]);
+        assert!(!outside[Coords3::new(2, 2, 2)]);
+        assert!(!outside[Coords3::new(4, 0, 0)]);
+        // everything except the shell, the wall, and the middle of the shell
+        assert_eq!(
+            outside.cells().iter().filter(|&&reached| reached).count(),
+            125 - 26 - 25 - 1
+        );
+        assert_eq!(
+            solid
+                .flood_fill(Coords3::new(1, 1, 1), |_, &solid| !solid)
+                .cells()
+                .iter()
+                .filter(|&&reached| reached)
+                .count(),
+            0
+        );
+
+        let shell = Grid3::from_fn(5, 5, 5, |coords| solid[coords] && coords.x < 4);
+        assert_eq!(shell.surface_area(), 9 * 6 + 6);
+        let voxels = shell.to_voxels(Vector3::new(-1, -1, -1));
+        assert_eq!(voxels.len(), 26);
+        assert_eq!(voxels.surface_area(), shell.surface_area());
+        assert_eq!(voxels.exterior_surface_area(), 9 * 6);
+    }
+}
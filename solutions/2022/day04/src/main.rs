@@ -1,20 +1,21 @@
 use std::{
-    ops::Range,
     str::FromStr,
     sync::{mpsc, Arc},
 };
 
-use shared::{read_input, receive_answers, run_part_threaded, ValueError};
+use shared::{intervals::IntervalSet, read_input, receive_answers, run_part_threaded, ValueError};
 
-struct Assignment(Range<usize>);
+/// the sections assigned to an elf
+struct Assignment(IntervalSet<usize>);
 
 impl Assignment {
-    fn contains(&self, other: &Assignment) -> bool {
-        self.0.start >= other.0.start && self.0.end <= other.0.end
+    /// whether every section of this assignment is also in `other`
+    fn contained_by(&self, other: &Assignment) -> bool {
+        self.0.is_subset(&other.0)
     }
 
     fn overlaps(&self, other: &Assignment) -> bool {
-        self.0.end >= other.0.start && other.0.end >= self.0.start
+        self.0.intersects(&other.0)
     }
 }
 
@@ -31,9 +32,10 @@ impl FromStr for Assignment {
         let end = parts
             .next()
             .ok_or_else(|| ValueError(s.into()))?
-            .parse()
+            .parse::<usize>()
             .map_err(|_| ValueError(s.into()))?;
-        Ok(Assignment(Range { start, end }))
+        // the assignment includes the last section
+        Ok(Assignment(IntervalSet::from(start..end + 1)))
     }
 }
 
@@ -68,7 +70,7 @@ fn parse_input(input: &str) -> Box<dyn Iterator<Item = (Assignment, Assignment)>
 
 fn part1(input: &str) -> u32 {
     parse_input(input)
-        .filter(|(a, b)| a.contains(b) || b.contains(a))
+        .filter(|(a, b)| a.contained_by(b) || b.contained_by(a))
         .count() as u32
 }
 
@@ -102,7 +104,7 @@ mod test {
         let input = "1-2";
         assert_eq!(
             input.parse::<Assignment>().expect("test").0,
-            Range { start: 1, end: 2 }
+            IntervalSet::from(1..3)
         );
     }
 
@@ -1,176 +1,11 @@
-use std::{
-    fmt,
-    iter::FusedIterator,
-    sync::{mpsc, Arc},
-};
+use std::sync::{mpsc, Arc};
 
 use shared::{
     read_input, receive_answers, run_part_threaded,
-    types_2d::{iter_coords, Coords, Direction, Size},
+    types_2d::{Grid, Visibility},
 };
 
-struct HeightMap {
-    heights: Vec<Vec<u32>>,
-}
-
-impl HeightMap {
-    fn get(&self, coords: Coords) -> Option<u32> {
-        self.heights
-            .get(coords.y)
-            .and_then(|row| row.get(coords.x))
-            .copied()
-    }
-
-    fn dimensions(&self) -> Size {
-        Size {
-            width: self.heights[0].len(),
-            height: self.heights.len(),
-        }
-    }
-}
-
-impl fmt::Debug for HeightMap {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for row in self.heights.iter() {
-            for height in row {
-                write!(f, "{height}")?;
-            }
-            writeln!(f)?;
-        }
-        Ok(())
-    }
-}
-
-struct HeightMapIter<'a> {
-    direction: Direction,
-    height_map: &'a HeightMap,
-    coords: Coords,
-}
-
-struct HeightMapVisibleIter<'a> {
-    direction: Direction,
-    height_map: &'a HeightMap,
-    coords: Coords,
-    initial_height: u32,
-    // tracks whether we've previously seen a height that blocks our vision
-    vision_blocked: bool,
-}
-
-impl HeightMap {
-    /// iter each height between the height at the given coords and the edge in the given direction
-    fn heights_from_point(&self, coords: Coords, direction: Direction) -> HeightMapIter {
-        HeightMapIter {
-            direction,
-            height_map: self,
-            coords,
-        }
-    }
-
-    /// iter each height between the height at the given coords and the edge in the given direction
-    /// until (and including!) a height that is >= the starting coords' height.
-    fn visible_heights_from_point(
-        &self,
-        coords: Coords,
-        direction: Direction,
-    ) -> HeightMapVisibleIter {
-        HeightMapVisibleIter {
-            direction,
-            height_map: self,
-            coords,
-            initial_height: self.get(coords).expect("initial height"),
-            vision_blocked: false,
-        }
-    }
-
-    /// iter every Coord from left to right and top to bottom
-    fn iter_coords(&self) -> impl Iterator<Item = Coords> {
-        iter_coords(&self.dimensions())
-    }
-}
-
-impl Iterator for HeightMapIter<'_> {
-    type Item = u32;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        match self.direction {
-            Direction::Up => {
-                self.coords.y = match self.coords.y.checked_sub(1) {
-                    Some(n) => n,
-                    None => return None,
-                }
-            }
-            Direction::Right => {
-                self.coords.x = match self.coords.x.checked_add(1) {
-                    Some(n) => n,
-                    None => return None,
-                }
-            }
-            Direction::Down => {
-                self.coords.y = match self.coords.y.checked_add(1) {
-                    Some(n) => n,
-                    None => return None,
-                }
-            }
-            Direction::Left => {
-                self.coords.x = match self.coords.x.checked_sub(1) {
-                    Some(n) => n,
-                    None => return None,
-                }
-            }
-        };
-
-        self.height_map.get(self.coords)
-    }
-}
-
-impl FusedIterator for HeightMapIter<'_> {}
-
-impl Iterator for HeightMapVisibleIter<'_> {
-    type Item = u32;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.vision_blocked {
-            return None;
-        }
-        match self.direction {
-            Direction::Up => {
-                self.coords.y = match self.coords.y.checked_sub(1) {
-                    Some(n) => n,
-                    None => return None,
-                }
-            }
-            Direction::Right => {
-                self.coords.x = match self.coords.x.checked_add(1) {
-                    Some(n) => n,
-                    None => return None,
-                }
-            }
-            Direction::Down => {
-                self.coords.y = match self.coords.y.checked_add(1) {
-                    Some(n) => n,
-                    None => return None,
-                }
-            }
-            Direction::Left => {
-                self.coords.x = match self.coords.x.checked_sub(1) {
-                    Some(n) => n,
-                    None => return None,
-                }
-            }
-        };
-
-        if let Some(height) = self.height_map.get(self.coords) {
-            if height >= self.initial_height {
-                self.vision_blocked = true;
-            }
-            Some(height)
-        } else {
-            None
-        }
-    }
-}
-
-impl FusedIterator for HeightMapVisibleIter<'_> {}
+type HeightMap = Grid<u32>;
 
 fn main() {
     let (tx, rx) = mpsc::channel();
@@ -184,60 +19,83 @@ fn main() {
 }
 
 fn parse_input(input: &str) -> HeightMap {
-    HeightMap {
-        heights: input
-            .lines()
-            .map(|row| {
-                row.chars()
-                    .map(|ch| ch.to_digit(10).expect("digit"))
-                    .collect()
-            })
-            .collect(),
-    }
+    Grid::from_str_with(input, |ch, _| ch.to_digit(10)).expect("valid input")
 }
 
 fn part1(input: &str) -> usize {
-    use Direction::*;
     let trees = parse_input(input);
-    trees
-        .iter_coords()
-        .filter(|coords| {
-            let this_tree = trees.get(*coords).expect("get tree");
-            trees
-                .heights_from_point(*coords, Up)
-                .all(|height| height < this_tree)
-                || trees
-                    .heights_from_point(*coords, Right)
-                    .all(|height| height < this_tree)
-                || trees
-                    .heights_from_point(*coords, Down)
-                    .all(|height| height < this_tree)
-                || trees
-                    .heights_from_point(*coords, Left)
-                    .all(|height| height < this_tree)
-        })
-        .count()
+    Visibility::of(&trees).visible_count()
 }
 
 fn part2(input: &str) -> usize {
-    use Direction::*;
     let trees = parse_input(input);
+    let visibility = Visibility::of(&trees);
     trees
         .iter_coords()
-        .map(|coords| {
-            trees.visible_heights_from_point(coords, Up).count()
-                * trees.visible_heights_from_point(coords, Right).count()
-                * trees.visible_heights_from_point(coords, Down).count()
-                * trees.visible_heights_from_point(coords, Left).count()
-        })
+        .map(|coords| visibility.scenic_score(coords))
         .max()
         .unwrap()
 }
 
 #[cfg(test)]
 mod test {
+    use shared::{
+        testing::Lcg,
+        types_2d::{Coords, Direction},
+    };
+
     use super::*;
 
+    // the straightforward versions, looking along every row and column from every tree
+
+    /// whether the tree at `coords` can be seen from outside the grid, looking along a row or column
+    fn is_visible(trees: &HeightMap, coords: Coords) -> bool {
+        let height = trees[coords];
+        Direction::all().any(|direction| {
+            trees
+                .ray(coords, direction)
+                .all(|(_, &other)| other < height)
+        })
+    }
+
+    /// how many trees can be seen from the tree at `coords` in each direction, multiplied together
+    fn scenic_score(trees: &HeightMap, coords: Coords) -> usize {
+        let height = trees[coords];
+        Direction::all()
+            .map(|direction| {
+                trees
+                    .ray(coords, direction)
+                    .take_until_inclusive(|(_, &other)| other >= height)
+                    .count()
+            })
+            .product()
+    }
+
+    #[test]
+    fn test_matches_naive() {
+        let mut rng = Lcg::new(8);
+        for _ in 0..200 {
+            let width = rng.below(8) as usize + 1;
+            let height = rng.below(8) as usize + 1;
+            // only a few different heights so there are lots of trees the same height as each other
+            let cells = (0..width * height).map(|_| rng.below(4) as u32).collect();
+            let trees = Grid::from_vec(width, height, cells).unwrap();
+            let visibility = Visibility::of(&trees);
+            for coords in trees.iter_coords() {
+                assert_eq!(
+                    visibility.is_visible(coords),
+                    is_visible(&trees, coords),
+                    "{coords:?} in\n{trees}"
+                );
+                assert_eq!(
+                    visibility.scenic_score(coords),
+                    scenic_score(&trees, coords),
+                    "{coords:?} in\n{trees}"
+                );
+            }
+        }
+    }
+
     const INPUT: &str = r"30373
 25512
 65332
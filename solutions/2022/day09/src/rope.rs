@@ -0,0 +1,126 @@
+use std::collections::HashSet;
+
+use shared::types_2d::{Direction, Direction8, InfGrid, Vector, Visited};
+
+/// where `follower` moves to so that it is still touching `leader`, including diagonally
+///
+/// if it is too far away, it moves one step directly or diagonally towards the leader
+pub fn follow(follower: Vector, leader: Vector) -> Vector {
+    let Vector { x: dx, y: dy } = leader - follower;
+    match Direction8::towards(leader - follower) {
+        Some(direction) if dx.abs() > 1 || dy.abs() > 1 => follower + Vector::from(direction),
+        _ => follower,
+    }
+}
+
+/// A rope made of knots, each one following the knot in front of it
+pub struct Rope {
+    /// the head is first and the tail is last
+    knots: Vec<Vector>,
+    /// every position the tail has been to
+    visited: HashSet<Vector>,
+}
+
+impl Rope {
+    /// a rope of `length` knots, all starting at the origin
+    pub fn new(length: usize) -> Self {
+        assert!(length > 0, "a rope needs at least one knot");
+        Rope {
+            knots: vec![Vector::zero(); length],
+            visited: HashSet::from([Vector::zero()]),
+        }
+    }
+
+    pub fn tail(&self) -> Vector {
+        *self.knots.last().expect("at least one knot")
+    }
+
+    /// move the head one step in `direction` and pull the rest of the rope along behind it
+    pub fn step(&mut self, direction: Direction) {
+        self.knots[0] = self.knots[0] + Vector::from(direction);
+        for i in 1..self.knots.len() {
+            let knot = follow(self.knots[i], self.knots[i - 1]);
+            if knot == self.knots[i] {
+                // the rest of the rope won't move either
+                break;
+            }
+            self.knots[i] = knot;
+        }
+        self.visited.insert(self.tail());
+    }
+
+    /// the number of positions the tail has visited, including the start
+    pub fn visited_count(&self) -> usize {
+        self.visited.len()
+    }
+
+    /// draw the rope into a grid, labelling each position with the index of the knot nearest the head
+    ///
+    /// positions the tail has visited are marked as visited, whether or not there is a knot there now
+    pub fn render(&self) -> InfGrid<usize> {
+        let mut grid = InfGrid::new();
+        for &position in &self.visited {
+            grid.mark(Visited, position);
+        }
+        for (index, &position) in self.knots.iter().enumerate().rev() {
+            grid.add(position, index);
+        }
+        grid
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_follow() {
+        let leader = Vector::zero();
+        // touching, overlapping or diagonally adjacent doesn't move
+        for x in -1..=1 {
+            for y in -1..=1 {
+                assert_eq!(follow(Vector::new(x, y), leader), Vector::new(x, y));
+            }
+        }
+        assert_eq!(follow(Vector::new(-2, 0), leader), Vector::new(-1, 0));
+        assert_eq!(follow(Vector::new(0, 2), leader), Vector::new(0, 1));
+        assert_eq!(follow(Vector::new(2, 1), leader), Vector::new(1, 0));
+        assert_eq!(follow(Vector::new(-2, -2), leader), Vector::new(-1, -1));
+    }
+
+    #[test]
+    fn test_any_length() {
+        // with one knot, the head is the tail
+        let mut rope = Rope::new(1);
+        for _ in 0..5 {
+            rope.step(Direction::Right);
+        }
+        assert_eq!(rope.visited_count(), 6);
+
+        // a long rope takes a while to straighten out before the tail moves
+        let mut rope = Rope::new(30);
+        for _ in 0..29 {
+            rope.step(Direction::Up);
+        }
+        assert_eq!(rope.visited_count(), 1);
+        rope.step(Direction::Up);
+        assert_eq!(rope.tail(), Vector::new(0, -1));
+        assert_eq!(rope.visited_count(), 2);
+    }
+
+    #[test]
+    fn test_render() {
+        let mut rope = Rope::new(2);
+        for _ in 0..3 {
+            rope.step(Direction::Right);
+        }
+        let grid = rope.render();
+        assert_eq!(grid.get(Vector::new(3, 0)), Some(&0));
+        assert_eq!(grid.get(Vector::new(2, 0)), Some(&1));
+        // the tail's trail is marked, without a knot where it has moved on from
+        assert_eq!(grid.count_marked(Visited), 3);
+        assert!(grid.is_marked(Visited, Vector::zero()));
+        assert_eq!(grid.get(Vector::zero()), None);
+        assert!(!grid.is_marked(Visited, Vector::new(3, 0)));
+    }
+}
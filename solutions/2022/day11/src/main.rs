@@ -1,10 +1,18 @@
 mod monkey;
+mod worry;
 
-use std::sync::{mpsc, Arc};
+use std::{
+    cmp::Reverse,
+    hash::Hash,
+    sync::{mpsc, Arc},
+};
 
 use monkey::{parse_monkey, Monkey};
+use worry::{ModInt, Worry, WorryOverflow};
 
-use shared::{read_input, receive_answers, run_part_threaded};
+use shared::{
+    arithmetic::checked_lcm, cycles::CycleDetector, read_input, receive_answers, run_part_threaded,
+};
 
 fn main() {
     let (tx, rx) = mpsc::channel();
@@ -21,53 +29,165 @@ fn parse_input(input: &str) -> impl Iterator<Item = Monkey> + '_ {
     input.split("\n\n").map(parse_monkey)
 }
 
-fn calculate_monkey_business<F>(mut monkeys: Vec<Monkey>, rounds: usize, worry_reducer: F) -> u64
+/// Simulate the monkeys passing items around, tracking each item's worry level as a `W`
+///
+/// Returns an error rather than a wrong answer if a worry level overflows.
+fn calculate_monkey_business<W, I, F>(
+    mut monkeys: Vec<Monkey>,
+    rounds: usize,
+    into_worry: I,
+    worry_reducer: F,
+) -> Result<u64, WorryOverflow>
 where
-    F: Fn(u64) -> u64,
+    W: Worry,
+    I: Fn(u64) -> W,
+    F: Fn(W) -> W,
 {
+    let mut items: Vec<Vec<W>> = monkeys
+        .iter()
+        .map(|monkey| monkey.items.iter().copied().map(&into_worry).collect())
+        .collect();
+
+    for _ in 0..rounds {
+        for (i, monkey) in monkeys.iter_mut().enumerate() {
+            while let Some(item) = items[i].pop() {
+                let item = item.apply(monkey.operation)?;
+                monkey.inspection_count += 1;
+                let item = worry_reducer(item);
+
+                let to = if item.is_divisible_by(monkey.test) {
+                    monkey.if_true
+                } else {
+                    monkey.if_false
+                };
+
+                items[to].push(item);
+            }
+        }
+    }
+    Ok(monkey_business(monkeys))
+}
+
+/// Simulate the same thing as [`calculate_monkey_business`], but follow each item on its own
+///
+/// Items never affect each other, so the rounds an item goes through start repeating as soon as
+/// it is back with the same monkey at the same worry level, and the remaining rounds can be skipped.
+fn calculate_monkey_business_by_item<W, I, F>(
+    mut monkeys: Vec<Monkey>,
+    rounds: usize,
+    into_worry: I,
+    worry_reducer: F,
+) -> Result<u64, WorryOverflow>
+where
+    W: Worry + Hash + Eq,
+    I: Fn(u64) -> W,
+    F: Fn(W) -> W,
+{
+    let items: Vec<(usize, W)> = monkeys
+        .iter()
+        .enumerate()
+        .flat_map(|(i, monkey)| monkey.items.iter().map(move |&item| (i, item)))
+        .map(|(i, item)| (i, into_worry(item)))
+        .collect();
+
+    for item in items {
+        let inspections = inspections_of_item(&monkeys, item, rounds, &worry_reducer)?;
+        for (monkey, count) in monkeys.iter_mut().zip(inspections) {
+            monkey.inspection_count += count;
+        }
+    }
+    Ok(monkey_business(monkeys))
+}
+
+/// count how many times each monkey inspects an item that starts with the given monkey
+fn inspections_of_item<W, F>(
+    monkeys: &[Monkey],
+    item: (usize, W),
+    rounds: usize,
+    worry_reducer: F,
+) -> Result<Vec<u64>, WorryOverflow>
+where
+    W: Worry + Hash + Eq,
+    F: Fn(W) -> W,
+{
+    let mut detector = CycleDetector::new();
+    // how many times each monkey inspected the item in each round
+    let mut per_round: Vec<Vec<u64>> = Vec::new();
+    let mut state = item;
+
     for _ in 0..rounds {
-        for i in 0..monkeys.len() {
-            {
-                while let Some(item) = monkeys[i].items.pop() {
-                    let monkey = &mut monkeys[i];
-
-                    let item = (monkey.operation)(item);
-                    monkey.inspection_count += 1;
-                    let item = worry_reducer(item);
-
-                    let to = if item % monkey.test == 0 {
-                        monkey.if_true
-                    } else {
-                        monkey.if_false
-                    };
-
-                    monkeys[to].items.push(item);
-                }
+        if let Some(cycle) = detector.record(&state) {
+            return Ok((0..monkeys.len())
+                .map(|i| {
+                    let per_round: Vec<u64> = per_round.iter().map(|counts| counts[i]).collect();
+                    cycle.extrapolate_sum(&per_round, rounds)
+                })
+                .collect());
+        }
+
+        let mut counts = vec![0; monkeys.len()];
+        let (mut holder, mut item) = state;
+        loop {
+            let monkey = &monkeys[holder];
+            item = worry_reducer(item.apply(monkey.operation)?);
+            counts[holder] += 1;
+
+            let to = if item.is_divisible_by(monkey.test) {
+                monkey.if_true
+            } else {
+                monkey.if_false
+            };
+
+            // monkeys take their turns in order, so an item thrown to an earlier monkey waits for the next round
+            let next_round = to < holder;
+            holder = to;
+            if next_round {
+                break;
             }
         }
+        per_round.push(counts);
+        state = (holder, item);
     }
-    monkeys.sort_by(|a, b| b.inspection_count.cmp(&a.inspection_count));
+
+    Ok((0..monkeys.len())
+        .map(|i| per_round.iter().map(|counts| counts[i]).sum())
+        .collect())
+}
+
+/// the product of the inspection counts of the two most active monkeys
+fn monkey_business(mut monkeys: Vec<Monkey>) -> u64 {
+    monkeys.sort_by_key(|monkey| Reverse(monkey.inspection_count));
     monkeys[0].inspection_count * monkeys[1].inspection_count
 }
 
 fn part1(input: &str) -> u64 {
     let monkeys: Vec<Monkey> = parse_input(input).collect();
-    calculate_monkey_business(monkeys, 20, |item| item / 3)
+    calculate_monkey_business(monkeys, 20, |item| item, |item| item / 3)
+        .expect("worry levels should fit in a u64")
 }
 
 fn part2(input: &str) -> u64 {
     let monkeys: Vec<Monkey> = parse_input(input).collect();
 
-    // this relies on the fact that all the divisibility tests are for prime numbers
-    let lcm: u64 = monkeys.iter().map(|m| m.test).product();
-
     // item modulo lcm will still have the same result for *any* monkey's divisibility test
-    calculate_monkey_business(monkeys, 10_000, |item| item % lcm)
+    let modulus = monkeys
+        .iter()
+        .try_fold(1, |acc, monkey| checked_lcm(acc, monkey.test))
+        .expect("lcm of the divisibility tests should fit in a u64");
+
+    calculate_monkey_business_by_item(
+        monkeys,
+        10_000,
+        |item| ModInt::new(item, modulus),
+        |item| item,
+    )
+    .expect("modular worry levels cannot overflow")
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use worry::Operation;
 
     const INPUT: &str = r"Monkey 0:
 Starting items: 79, 98
@@ -101,7 +221,8 @@ Test: divisible by 17
     fn test_parse_input() {
         let monkeys = parse_input(INPUT).collect::<Vec<Monkey>>();
         assert_eq!(monkeys[0].items, vec![79, 98]);
-        assert_eq!((monkeys[1].operation)(5), 5 + 6);
+        assert_eq!(5.apply(monkeys[1].operation), Ok(5 + 6));
+        assert_eq!(monkeys[2].operation, Operation::Square);
         assert_eq!(monkeys[2].test, 13);
         assert_eq!(monkeys[3].if_true, 0);
         assert_eq!(monkeys[3].if_false, 1);
@@ -116,4 +237,61 @@ Test: divisible by 17
     fn test_part2() {
         assert_eq!(part2(INPUT), 2713310158);
     }
+
+    #[test]
+    fn test_by_item_matches_by_round() {
+        for rounds in [1, 20, 1000] {
+            let by_round = calculate_monkey_business(
+                parse_input(INPUT).collect(),
+                rounds,
+                |item| item,
+                |item| item / 3,
+            );
+            let by_item = calculate_monkey_business_by_item(
+                parse_input(INPUT).collect(),
+                rounds,
+                |item| item,
+                |item| item / 3,
+            );
+            assert_eq!(by_round, by_item, "rounds = {rounds}");
+
+            let modulus = 23 * 19 * 13 * 17;
+            let by_round = calculate_monkey_business(
+                parse_input(INPUT).collect(),
+                rounds,
+                |item| ModInt::new(item, modulus),
+                |item| item,
+            );
+            let by_item = calculate_monkey_business_by_item(
+                parse_input(INPUT).collect(),
+                rounds,
+                |item| ModInt::new(item, modulus),
+                |item| item,
+            );
+            assert_eq!(by_round, by_item, "rounds = {rounds}");
+        }
+    }
+
+    #[test]
+    fn test_overflow_is_an_error() {
+        let monkeys = vec![
+            Monkey {
+                items: vec![1 << 32],
+                operation: Operation::Square,
+                test: 2,
+                if_true: 1,
+                if_false: 1,
+                inspection_count: 0,
+            },
+            Monkey {
+                items: vec![],
+                operation: Operation::Square,
+                test: 2,
+                if_true: 0,
+                if_false: 0,
+                inspection_count: 0,
+            },
+        ];
+        assert!(calculate_monkey_business(monkeys, 1, |item| item, |item| item).is_err());
+    }
 }
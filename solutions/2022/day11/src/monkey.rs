@@ -1,8 +1,10 @@
+use crate::worry::Operation;
+
 pub struct Monkey {
     /// current items
     pub items: Vec<u64>,
-    /// apply this function to each item to get a new item
-    pub operation: Box<dyn Fn(u64) -> u64>,
+    /// apply this operation to each item to get a new item
+    pub operation: Operation,
     pub test: u64,
     pub if_true: usize,
     pub if_false: usize,
@@ -23,23 +25,17 @@ pub fn parse_monkey(input: &str) -> Monkey {
         .find(['+', '*'])
         .expect("valid input");
     let (_, operation) = operation.split_at(split_at_index);
-    let operation: Box<dyn Fn(u64) -> u64> = if operation.starts_with('+') {
+    let operation = if operation.starts_with('+') {
         let digits: String = operation.chars().skip(2).collect();
         match digits.as_str() {
-            "old" => Box::new(|value: u64| value + value),
-            val => {
-                let val = val.parse::<u64>().expect("valid input");
-                Box::new(move |value: u64| value + val)
-            }
+            "old" => Operation::Double,
+            val => Operation::Add(val.parse::<u64>().expect("valid input")),
         }
     } else {
         let digits: String = operation.chars().skip(2).collect();
         match digits.as_str() {
-            "old" => Box::new(|value: u64| value * value),
-            val => {
-                let val = val.parse::<u64>().expect("valid input");
-                Box::new(move |value: u64| value * val)
-            }
+            "old" => Operation::Square,
+            val => Operation::Multiply(val.parse::<u64>().expect("valid input")),
         }
     };
 
@@ -0,0 +1,140 @@
+use std::fmt;
+
+/// an operation a monkey applies to an item's worry level when inspecting it
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Operation {
+    /// new = old + n
+    Add(u64),
+    /// new = old * n
+    Multiply(u64),
+    /// new = old + old
+    Double,
+    /// new = old * old
+    Square,
+}
+
+/// A worry level grew too large to be represented exactly
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct WorryOverflow {
+    pub worry: u64,
+    pub operation: Operation,
+}
+
+impl std::error::Error for WorryOverflow {}
+impl fmt::Display for WorryOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "worry level {} overflowed when applying {:?}",
+            self.worry, self.operation
+        )
+    }
+}
+
+/// Something that can be used to track the worry level of an item
+pub trait Worry: Copy {
+    fn apply(self, operation: Operation) -> Result<Self, WorryOverflow>;
+
+    fn is_divisible_by(self, divisor: u64) -> bool;
+}
+
+/// plain worry levels use checked arithmetic so that overflow is an error rather than a wrong answer
+impl Worry for u64 {
+    fn apply(self, operation: Operation) -> Result<Self, WorryOverflow> {
+        let new = match operation {
+            Operation::Add(n) => self.checked_add(n),
+            Operation::Multiply(n) => self.checked_mul(n),
+            Operation::Double => self.checked_add(self),
+            Operation::Square => self.checked_mul(self),
+        };
+        new.ok_or(WorryOverflow {
+            worry: self,
+            operation,
+        })
+    }
+
+    fn is_divisible_by(self, divisor: u64) -> bool {
+        self.is_multiple_of(divisor)
+    }
+}
+
+/// An integer modulo some fixed modulus
+///
+/// Arithmetic is done in u128 before reducing, so it can never overflow.
+/// Divisibility checks are only meaningful for divisors of the modulus.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ModInt {
+    value: u64,
+    modulus: u64,
+}
+
+impl ModInt {
+    /// panics if modulus is zero
+    pub fn new(value: u64, modulus: u64) -> Self {
+        assert!(modulus > 0, "modulus must be greater than zero");
+        ModInt {
+            value: value % modulus,
+            modulus,
+        }
+    }
+
+    fn reduce(&self, n: u128) -> Self {
+        ModInt {
+            // n % modulus is always less than modulus, so it fits back in a u64
+            value: (n % self.modulus as u128) as u64,
+            modulus: self.modulus,
+        }
+    }
+}
+
+impl Worry for ModInt {
+    fn apply(self, operation: Operation) -> Result<Self, WorryOverflow> {
+        let value = self.value as u128;
+        let new = match operation {
+            Operation::Add(n) => value + n as u128,
+            Operation::Multiply(n) => value * n as u128,
+            Operation::Double => value + value,
+            Operation::Square => value * value,
+        };
+        Ok(self.reduce(new))
+    }
+
+    fn is_divisible_by(self, divisor: u64) -> bool {
+        debug_assert!(
+            self.modulus.is_multiple_of(divisor),
+            "divisibility by {divisor} is not preserved modulo {}",
+            self.modulus
+        );
+        self.value.is_multiple_of(divisor)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_u64_overflow_is_an_error() {
+        assert_eq!(3u64.apply(Operation::Square), Ok(9));
+        assert_eq!(
+            u64::MAX.apply(Operation::Square),
+            Err(WorryOverflow {
+                worry: u64::MAX,
+                operation: Operation::Square
+            })
+        );
+        assert!(u64::MAX.apply(Operation::Add(1)).is_err());
+    }
+
+    #[test]
+    fn test_mod_int_does_not_overflow() {
+        let modulus = u64::MAX - 1;
+        let worry = ModInt::new(u64::MAX - 2, modulus);
+        // (-1)^2 == 1 (mod m)
+        assert_eq!(worry.apply(Operation::Square).unwrap().value, 1);
+        // -1 + 5 == 4 (mod m)
+        assert_eq!(worry.apply(Operation::Add(5)).unwrap().value, 4);
+        // -1 + -1 == m - 2 (mod m)
+        assert_eq!(worry.apply(Operation::Double).unwrap().value, modulus - 2);
+    }
+}
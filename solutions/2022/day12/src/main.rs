@@ -27,8 +27,7 @@ impl HeightMap {
 
     fn plot_route(&self, path: &Vec<Coords>) {
         let Size { width, height } = self.dimensions();
-        let blanks = vec![vec!['.'; width]; height];
-        let mut grid = Grid::new(blanks);
+        let mut grid = Grid::filled(width, height, '.');
 
         for coords in path {
             if let Some(ch) = grid.get_mut(*coords) {
@@ -42,13 +41,7 @@ impl HeightMap {
 
 impl fmt::Debug for HeightMap {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for row in self.heights.cells.iter() {
-            for height in row {
-                write!(f, "{}", *height as char)?;
-            }
-            writeln!(f)?;
-        }
-        Ok(())
+        write!(f, "{}", self.heights.display_with(|&height| height as char))
     }
 }
 
@@ -121,33 +114,16 @@ fn main() {
 }
 
 fn parse_input(input: &str) -> HeightMap {
-    let mut start = Coords::zero();
-    let mut end = Coords::zero();
-
-    let heights = input
-        .lines()
-        .enumerate()
-        .map(|(y, line)| {
-            line.chars()
-                .enumerate()
-                .map(|(x, ch)| match ch {
-                    'S' => {
-                        start = Coords { x, y };
-                        b'a'
-                    }
-                    'E' => {
-                        end = Coords { x, y };
-                        b'z'
-                    }
-                    ch => ch as u8,
-                })
-                .collect::<Vec<_>>()
-        })
-        .collect::<Vec<_>>();
+    let (heights, markers) = Grid::from_str_with_markers(input, &['S', 'E'], |ch, _| match ch {
+        'S' => Some(b'a'),
+        'E' => Some(b'z'),
+        ch => ch.is_ascii_lowercase().then_some(ch as u8),
+    })
+    .expect("valid input");
     HeightMap {
-        heights: Grid::new(heights),
-        start,
-        end,
+        heights,
+        start: markers[&'S'][0],
+        end: markers[&'E'][0],
     }
 }
 
@@ -180,6 +156,17 @@ accszExk
 acctuvwj
 abdefghi";
 
+    #[test]
+    fn test_parser() {
+        let height_map = parse_input(INPUT);
+        assert_eq!(height_map.start, Coords { x: 0, y: 0 });
+        assert_eq!(height_map.end, Coords { x: 5, y: 2 });
+        assert_eq!(
+            format!("{height_map:?}").trim(),
+            INPUT.replace('S', "a").replace('E', "z")
+        );
+    }
+
     #[test]
     fn test_height_neighbours() {
         let height_map = parse_input(INPUT);
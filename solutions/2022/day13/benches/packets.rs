@@ -0,0 +1,150 @@
+//! Compares the hand written packet parser against the serde_json based parsing day13 used to use
+//!
+//! run with `cargo bench -p aoc-2022-day13`
+
+use std::{
+    cmp::Ordering,
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use aoc_2022_day13::packet::{compare_packets, position_of, Packet};
+use serde::Deserialize;
+use shared::testing::Lcg;
+
+/// the serde based packet day13 used before it had its own parser, copied as it was so the comparison is fair
+#[derive(Deserialize, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+enum Expr {
+    List(Vec<Expr>),
+    Int(u32),
+}
+
+impl Expr {
+    fn with_slice<T>(&self, f: impl FnOnce(&[Expr]) -> T) -> T {
+        match self {
+            Expr::List(list) => f(list.as_slice()),
+            int => {
+                // cloned into a new slice as the old code did, rather than borrowed with `slice::from_ref`
+                let list = [int.clone()];
+                f(&list)
+            }
+        }
+    }
+}
+
+impl Ord for Expr {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Expr::Int(a), Expr::Int(b)) => a.cmp(b),
+            (a, b) => a.with_slice(|a| {
+                b.with_slice(|b| {
+                    a.iter()
+                        .zip(b.iter())
+                        .map(|(a, b)| a.cmp(b))
+                        .find(|&ordering| ordering != Ordering::Equal)
+                        .unwrap_or_else(|| a.len().cmp(&b.len()))
+                })
+            }),
+        }
+    }
+}
+
+impl PartialOrd for Expr {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn random_packet(rng: &mut Lcg, depth: u32) -> Packet {
+    if depth > 0 && rng.below(3) > 0 {
+        let len = rng.below(6);
+        Packet::List((0..len).map(|_| random_packet(rng, depth - 1)).collect())
+    } else {
+        Packet::Int(rng.below(11) as u32)
+    }
+}
+
+fn random_lines(count: usize) -> Vec<String> {
+    let mut rng = Lcg::new(13);
+    (0..count)
+        .map(|_| Packet::List(vec![random_packet(&mut rng, 5), random_packet(&mut rng, 5)]))
+        .map(|packet| packet.to_string())
+        .collect()
+}
+
+fn bench(name: &str, mut f: impl FnMut() -> usize) {
+    const ITERATIONS: u32 = 20;
+    let mut total = Duration::ZERO;
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        black_box(f());
+        total += start.elapsed();
+    }
+    println!("{name:<32} {:>12.3?} per iteration", total / ITERATIONS);
+}
+
+fn main() {
+    let lines = random_lines(10_000);
+    let pairs: Vec<(&String, &String)> = lines.iter().zip(lines.iter().skip(1)).collect();
+
+    bench("parse: serde_json", || {
+        lines
+            .iter()
+            .filter(|line| serde_json::from_str::<Expr>(line).is_ok())
+            .count()
+    });
+    bench("parse: Packet", || {
+        lines
+            .iter()
+            .filter(|line| line.parse::<Packet>().is_ok())
+            .count()
+    });
+
+    bench("compare: serde_json", || {
+        pairs
+            .iter()
+            .filter(|(left, right)| {
+                let left: Expr = serde_json::from_str(left).expect("valid packet");
+                let right: Expr = serde_json::from_str(right).expect("valid packet");
+                left < right
+            })
+            .count()
+    });
+    bench("compare: Packet", || {
+        pairs
+            .iter()
+            .filter(|(left, right)| {
+                let left: Packet = left.parse().expect("valid packet");
+                let right: Packet = right.parse().expect("valid packet");
+                left < right
+            })
+            .count()
+    });
+    bench("compare: streaming", || {
+        pairs
+            .iter()
+            .filter(|(left, right)| {
+                compare_packets(left, right).expect("valid packet") == Ordering::Less
+            })
+            .count()
+    });
+
+    let packets: Vec<Packet> = lines
+        .iter()
+        .map(|line| line.parse().expect("valid packet"))
+        .collect();
+    let divider: Packet = "[[2]]".parse().expect("valid packet");
+
+    bench("rank: sort", || {
+        let mut packets = packets.clone();
+        packets.push(divider.clone());
+        packets.sort();
+        packets
+            .iter()
+            .rposition(|packet| *packet == divider)
+            .unwrap()
+            + 1
+    });
+    bench("rank: position_of", || position_of(&divider, &packets));
+}
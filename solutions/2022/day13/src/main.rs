@@ -1,59 +1,13 @@
 use std::{
     cmp::Ordering,
-    fmt,
     sync::{mpsc, Arc},
 };
 
-use serde::{Deserialize, Serialize};
+use aoc_2022_day13::packet::{compare_packets, position_of, Packet};
 use shared::{read_input, receive_answers, run_part_threaded};
 
 type Answer = usize;
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
-#[serde(untagged)]
-enum Expr {
-    List(Vec<Expr>),
-    Int(u32),
-}
-
-impl Expr {
-    fn with_slice<T>(&self, f: impl FnOnce(&[Expr]) -> T) -> T {
-        match self {
-            Expr::List(list) => f(list.as_slice()),
-            int => f(&[int.clone()]),
-        }
-    }
-}
-
-impl fmt::Debug for Expr {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", serde_json::to_string(&self).expect("debug"))
-    }
-}
-
-impl Ord for Expr {
-    fn cmp(&self, other: &Self) -> Ordering {
-        match (self, other) {
-            (Expr::Int(a), Expr::Int(b)) => a.cmp(b),
-            (a, b) => a.with_slice(|a| {
-                b.with_slice(|b| {
-                    a.iter()
-                        .zip(b.iter())
-                        .map(|(a, b)| a.cmp(b))
-                        .find(|&ordering| ordering != Ordering::Equal)
-                        .unwrap_or_else(|| a.len().cmp(&b.len()))
-                })
-            }),
-        }
-    }
-}
-
-impl PartialOrd for Expr {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
-    }
-}
-
 fn main() {
     let (tx, rx) = mpsc::channel();
     let input = read_input(2022, 13);
@@ -65,22 +19,23 @@ fn main() {
     receive_answers(rx);
 }
 
-fn parse_input_part1(input: &str) -> Vec<(Expr, Expr)> {
-    input
-        .split("\n\n")
-        .map(|pair| {
-            let mut exprs = pair
-                .lines()
-                .filter_map(|line| serde_json::from_str(line).ok());
-            (exprs.next().expect("input"), exprs.next().expect("input"))
-        })
-        .collect()
+/// pairs of packets, still as text so that they can be compared without parsing them first
+fn parse_input_part1(input: &str) -> impl Iterator<Item = (&str, &str)> {
+    input.split("\n\n").map(|pair| {
+        let mut lines = pair.lines();
+        (lines.next().expect("input"), lines.next().expect("input"))
+    })
 }
 
-fn parse_input_part2(input: &str) -> Vec<Expr> {
+fn parse_input_part2(input: &str) -> Vec<Packet> {
     input
         .lines()
-        .filter_map(|line| serde_json::from_str(line).ok())
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(i, line)| {
+            line.parse()
+                .unwrap_or_else(|err| panic!("line {}: {err}", i + 1))
+        })
         .collect()
 }
 
@@ -88,31 +43,27 @@ fn part1(input: &str) -> Answer {
     let mut sum = 0;
     let mut index = 0;
 
-    for pair in parse_input_part1(input) {
+    for (left, right) in parse_input_part1(input) {
         index += 1;
 
-        if pair.0 < pair.1 {
-            sum += index;
+        match compare_packets(left, right) {
+            Ok(Ordering::Less) => sum += index,
+            Ok(_) => {}
+            Err(err) => panic!("pair {index}: {err}"),
         }
     }
     sum
 }
 
 fn part2(input: &str) -> Answer {
-    let divider_one = Expr::List(vec![Expr::List(vec![Expr::Int(2)])]);
-    let divider_two = Expr::List(vec![Expr::List(vec![Expr::Int(6)])]);
-
-    let mut exprs = parse_input_part2(input);
-    exprs.push(divider_one.clone());
-    exprs.push(divider_two.clone());
-    exprs.sort();
-    exprs
-        .iter()
-        .enumerate()
-        .map(|(i, expr)| (i + 1, expr))
-        .filter(|(_, expr)| **expr == divider_one || **expr == divider_two)
-        .map(|(i, _)| i)
-        .product()
+    let divider_one = Packet::List(vec![Packet::List(vec![Packet::Int(2)])]);
+    let divider_two = Packet::List(vec![Packet::List(vec![Packet::Int(6)])]);
+
+    let packets = parse_input_part2(input);
+    // each divider's position has to account for the other divider being sorted in too
+    let position_one = position_of(&divider_one, packets.iter().chain([&divider_two]));
+    let position_two = position_of(&divider_two, packets.iter().chain([&divider_one]));
+    position_one * position_two
 }
 
 #[cfg(test)]
@@ -146,8 +97,8 @@ mod test {
     #[test]
     fn test_match_divider() {
         assert_eq!(
-            serde_json::from_str::<Expr>("[[2]]").unwrap(),
-            Expr::List(vec![Expr::List(vec![Expr::Int(2)])])
+            "[[2]]".parse::<Packet>().unwrap(),
+            Packet::List(vec![Packet::List(vec![Packet::Int(2)])])
         );
     }
 
@@ -0,0 +1,462 @@
+use std::{cmp::Ordering, fmt, slice, str::FromStr};
+
+/// A packet is either an integer or a list of packets
+///
+/// Equality is structural, so `3` and `[3]` are different packets even though they are in the same place when sorted.
+#[derive(Clone, PartialEq, Eq)]
+pub enum Packet {
+    List(Vec<Packet>),
+    Int(u32),
+}
+
+/// integers are compared to lists as if they were a list containing just that integer
+impl Ord for Packet {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Packet::Int(a), Packet::Int(b)) => a.cmp(b),
+            // slices compare item by item, then whichever runs out first is smaller
+            (Packet::List(a), Packet::List(b)) => a.as_slice().cmp(b.as_slice()),
+            (Packet::Int(_), Packet::List(b)) => slice::from_ref(self).cmp(b.as_slice()),
+            (Packet::List(a), Packet::Int(_)) => a.as_slice().cmp(slice::from_ref(other)),
+        }
+    }
+}
+
+impl PartialOrd for Packet {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// writes the packet exactly as it appears in the puzzle input, so parsing the output gives back the same packet
+impl fmt::Display for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Packet::Int(n) => write!(f, "{n}"),
+            Packet::List(list) => {
+                write!(f, "[")?;
+                for (i, packet) in list.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{packet}")?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{self}")
+    }
+}
+
+impl FromStr for Packet {
+    type Err = ParsePacketError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // the lists that have been opened but not yet closed
+        let mut open_lists: Vec<Vec<Packet>> = Vec::new();
+        let mut packet = None;
+
+        for token in Tokens::new(s) {
+            let complete = match token? {
+                Token::Open => {
+                    open_lists.push(Vec::new());
+                    continue;
+                }
+                Token::Int(n) => Packet::Int(n),
+                Token::Close => Packet::List(open_lists.pop().expect("tokens are balanced")),
+            };
+            match open_lists.last_mut() {
+                Some(parent) => parent.push(complete),
+                None => packet = Some(complete),
+            }
+        }
+
+        Ok(packet.expect("tokens only end after a complete packet"))
+    }
+}
+
+/// The position (counting from 1) that `packet` would have if it was sorted together with `others`
+///
+/// This only needs one comparison per packet, rather than sorting all of them.
+/// `others` should not include `packet` itself. Any of them that compare equal to `packet` count as being before it,
+/// like a stable sort with `packet` added to the end.
+pub fn position_of<'a>(packet: &Packet, others: impl IntoIterator<Item = &'a Packet>) -> usize {
+    1 + others
+        .into_iter()
+        .filter(|&other| other.cmp(packet) != Ordering::Greater)
+        .count()
+}
+
+/// Compare two packets directly from their text, without building either of them
+///
+/// This stops reading as soon as the order is known, so the rest of either packet is not checked for errors.
+pub fn compare_packets(left: &str, right: &str) -> Result<Ordering, ParsePacketError> {
+    let mut left = Promotable::new(left);
+    let mut right = Promotable::new(right);
+
+    loop {
+        match (left.next()?, right.next()?) {
+            (None, None) => return Ok(Ordering::Equal),
+            (Some(Token::Int(a)), Some(Token::Int(b))) => match a.cmp(&b) {
+                Ordering::Equal => continue,
+                ordering => return Ok(ordering),
+            },
+            (Some(Token::Open), Some(Token::Open)) | (Some(Token::Close), Some(Token::Close)) => {}
+            // an integer compared to a list is treated as a list containing just that integer
+            (Some(Token::Int(a)), Some(Token::Open)) => left.promote(a),
+            (Some(Token::Open), Some(Token::Int(b))) => right.promote(b),
+            // whichever list runs out first is smaller
+            (Some(Token::Close), _) => return Ok(Ordering::Less),
+            (_, Some(Token::Close)) => return Ok(Ordering::Greater),
+            // both packets are complete values, so they can only end at the same time as each other
+            (None, Some(_)) | (Some(_), None) => {
+                unreachable!("packets can only end after a matching close")
+            }
+        }
+    }
+}
+
+/// a token stream where an integer can be pushed back wrapped in a list
+struct Promotable<'a> {
+    tokens: Tokens<'a>,
+    pushed_back: Vec<Token>,
+}
+
+impl<'a> Promotable<'a> {
+    fn new(input: &'a str) -> Self {
+        Promotable {
+            tokens: Tokens::new(input),
+            pushed_back: Vec::new(),
+        }
+    }
+
+    fn next(&mut self) -> Result<Option<Token>, ParsePacketError> {
+        match self.pushed_back.pop() {
+            Some(token) => Ok(Some(token)),
+            None => self.tokens.next().transpose(),
+        }
+    }
+
+    /// the integer was read in place of an Open, so pretend it was `[n]`: the Open is already used up
+    fn promote(&mut self, n: u32) {
+        self.pushed_back.push(Token::Close);
+        self.pushed_back.push(Token::Int(n));
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Int(u32),
+}
+
+/// what the tokenizer will accept next
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Expect {
+    Value,
+    ValueOrClose,
+    CommaOrClose,
+    End,
+    Failed,
+}
+
+/// Splits packet text into tokens, checking the structure of the packet as it goes
+struct Tokens<'a> {
+    input: &'a str,
+    position: usize,
+    depth: usize,
+    expect: Expect,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(input: &'a str) -> Self {
+        Tokens {
+            input,
+            position: 0,
+            depth: 0,
+            expect: Expect::Value,
+        }
+    }
+
+    fn error(&mut self, kind: ParsePacketErrorKind) -> Option<Result<Token, ParsePacketError>> {
+        self.expect = Expect::Failed;
+        Some(Err(ParsePacketError {
+            position: self.position,
+            kind,
+        }))
+    }
+
+    fn after_value(&mut self) {
+        self.expect = if self.depth == 0 {
+            Expect::End
+        } else {
+            Expect::CommaOrClose
+        };
+    }
+
+    fn read_int(&mut self) -> Option<Result<Token, ParsePacketError>> {
+        let digits = self.input[self.position..]
+            .bytes()
+            .take_while(u8::is_ascii_digit)
+            .count();
+        match self.input[self.position..self.position + digits].parse() {
+            Ok(n) => {
+                self.position += digits;
+                self.after_value();
+                Some(Ok(Token::Int(n)))
+            }
+            Err(_) => self.error(ParsePacketErrorKind::IntegerOverflow),
+        }
+    }
+}
+
+impl Iterator for Tokens<'_> {
+    type Item = Result<Token, ParsePacketError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let next = self.input[self.position..].chars().next();
+            return match (self.expect, next) {
+                (Expect::Failed, _) | (Expect::End, None) => None,
+                (Expect::End, Some(_)) => self.error(ParsePacketErrorKind::TrailingInput),
+                (_, None) => self.error(ParsePacketErrorKind::UnexpectedEnd),
+                (Expect::Value | Expect::ValueOrClose, Some('[')) => {
+                    self.position += 1;
+                    self.depth += 1;
+                    self.expect = Expect::ValueOrClose;
+                    Some(Ok(Token::Open))
+                }
+                (Expect::Value | Expect::ValueOrClose, Some(ch)) if ch.is_ascii_digit() => {
+                    self.read_int()
+                }
+                (Expect::ValueOrClose | Expect::CommaOrClose, Some(']')) => {
+                    self.position += 1;
+                    self.depth -= 1;
+                    self.after_value();
+                    Some(Ok(Token::Close))
+                }
+                (Expect::CommaOrClose, Some(',')) => {
+                    // commas only separate values, they don't need a token of their own
+                    self.position += 1;
+                    self.expect = Expect::Value;
+                    continue;
+                }
+                (_, Some(ch)) => self.error(ParsePacketErrorKind::UnexpectedChar(ch)),
+            };
+        }
+    }
+}
+
+/// A packet could not be parsed, `position` is the byte offset of the problem
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParsePacketError {
+    pub position: usize,
+    pub kind: ParsePacketErrorKind,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ParsePacketErrorKind {
+    UnexpectedChar(char),
+    UnexpectedEnd,
+    IntegerOverflow,
+    TrailingInput,
+}
+
+impl std::error::Error for ParsePacketError {}
+impl fmt::Display for ParsePacketError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid packet at position {}: ", self.position)?;
+        match self.kind {
+            ParsePacketErrorKind::UnexpectedChar(ch) => write!(f, "unexpected {ch:?}"),
+            ParsePacketErrorKind::UnexpectedEnd => write!(f, "unexpected end of packet"),
+            ParsePacketErrorKind::IntegerOverflow => write!(f, "integer is too large"),
+            ParsePacketErrorKind::TrailingInput => write!(f, "unexpected input after packet"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use Packet::*;
+
+    fn error(position: usize, kind: ParsePacketErrorKind) -> Result<Packet, ParsePacketError> {
+        Err(ParsePacketError { position, kind })
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("[]".parse(), Ok(List(vec![])));
+        assert_eq!("7".parse(), Ok(Int(7)));
+        assert_eq!(
+            "[1,[2,[]],30]".parse(),
+            Ok(List(vec![
+                Int(1),
+                List(vec![Int(2), List(vec![])]),
+                Int(30)
+            ]))
+        );
+        // list nesting is kept, even where it makes no difference to the ordering
+        assert_eq!("[[3]]".parse(), Ok(List(vec![List(vec![Int(3)])])));
+        assert_ne!("[[3]]".parse(), Ok(List(vec![Int(3)])));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        use ParsePacketErrorKind::*;
+
+        assert_eq!("".parse::<Packet>(), error(0, UnexpectedEnd));
+        assert_eq!("[1,2".parse::<Packet>(), error(4, UnexpectedEnd));
+        assert_eq!("[1,,2]".parse::<Packet>(), error(3, UnexpectedChar(',')));
+        assert_eq!("[,]".parse::<Packet>(), error(1, UnexpectedChar(',')));
+        assert_eq!("[1,]".parse::<Packet>(), error(3, UnexpectedChar(']')));
+        assert_eq!("[1 2]".parse::<Packet>(), error(2, UnexpectedChar(' ')));
+        assert_eq!("[a]".parse::<Packet>(), error(1, UnexpectedChar('a')));
+        assert_eq!("[]]".parse::<Packet>(), error(2, TrailingInput));
+        assert_eq!("[][]".parse::<Packet>(), error(2, TrailingInput));
+        assert_eq!("[99999999999]".parse::<Packet>(), error(1, IntegerOverflow));
+    }
+
+    #[test]
+    fn test_display_round_trips() {
+        for line in [
+            "[]",
+            "[[[]]]",
+            "[1,1,3,1,1]",
+            "[[1],[2,3,4]]",
+            "[1,[2,[3,[4,[5,6,7]]]],8,9]",
+            "[10,[],[[0]]]",
+        ] {
+            let packet: Packet = line.parse().unwrap();
+            assert_eq!(packet.to_string(), line);
+            assert_eq!(format!("{packet:?}"), line);
+        }
+    }
+
+    #[test]
+    fn test_compare_packets_matches_ord() {
+        let packets = [
+            "[1,1,3,1,1]",
+            "[1,1,5,1,1]",
+            "[[1],[2,3,4]]",
+            "[[1],4]",
+            "[9]",
+            "[[8,7,6]]",
+            "[[4,4],4,4]",
+            "[[4,4],4,4,4]",
+            "[7,7,7,7]",
+            "[7,7,7]",
+            "[]",
+            "[3]",
+            "[[[]]]",
+            "[[]]",
+            "[1,[2,[3,[4,[5,6,7]]]],8,9]",
+            "[1,[2,[3,[4,[5,6,0]]]],8,9]",
+            "[[2]]",
+            "[[[[2]]]]",
+            "[2]",
+            "[[6]]",
+            "[[[6],7]]",
+            "[[6,[]]]",
+        ];
+        for left in packets {
+            for right in packets {
+                let expected = left
+                    .parse::<Packet>()
+                    .unwrap()
+                    .cmp(&right.parse::<Packet>().unwrap());
+                assert_eq!(
+                    compare_packets(left, right),
+                    Ok(expected),
+                    "{left} vs {right}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_int_compares_as_list() {
+        assert_eq!(Int(3).cmp(&List(vec![Int(3)])), Ordering::Equal);
+        assert_eq!(Int(3).cmp(&List(vec![List(vec![Int(3)])])), Ordering::Equal);
+        assert_eq!(Int(3).cmp(&List(vec![Int(3), Int(0)])), Ordering::Less);
+        assert_eq!(List(vec![]).cmp(&Int(0)), Ordering::Less);
+        assert_eq!(List(vec![Int(4)]).cmp(&Int(3)), Ordering::Greater);
+
+        // differently nested packets can tie without being equal
+        assert_eq!(Int(3).cmp(&List(vec![Int(3)])), Ordering::Equal);
+        assert_eq!(
+            List(vec![List(vec![Int(2)])]).cmp(&List(vec![Int(2)])),
+            Ordering::Equal
+        );
+        assert_ne!(Int(3), List(vec![Int(3)]));
+    }
+
+    #[test]
+    fn test_position_of_matches_sort() {
+        let mut packets: Vec<Packet> = [
+            "[1,1,3,1,1]",
+            "[[1],[2,3,4]]",
+            "[[8,7,6]]",
+            "[[4,4],4,4,4]",
+            "[7,7,7]",
+            "[3]",
+            "[[]]",
+            "[1,[2,[3,[4,[5,6,0]]]],8,9]",
+        ]
+        .into_iter()
+        .map(|line| line.parse().unwrap())
+        .collect();
+
+        for (i, packet) in packets.iter().enumerate() {
+            let others = packets[..i].iter().chain(&packets[i + 1..]);
+            let position = position_of(packet, others);
+
+            let mut sorted = packets.clone();
+            sorted.sort();
+            assert_eq!(sorted[position - 1], *packet);
+        }
+
+        packets.sort();
+        let divider = "[[2]]".parse().unwrap();
+        assert_eq!(position_of(&divider, &packets), 5);
+    }
+
+    #[test]
+    fn test_position_of_ties() {
+        let divider: Packet = "[[2]]".parse().unwrap();
+        let packets: Vec<Packet> = ["[1]", "[2]", "[[[2]]]", "[3]"]
+            .into_iter()
+            .map(|line| line.parse().unwrap())
+            .collect();
+        // [2] and [[[2]]] tie with the divider, so it goes after both of them
+        assert_eq!(position_of(&divider, &packets), 4);
+
+        let mut sorted = packets.clone();
+        sorted.push(divider.clone());
+        sorted.sort();
+        let position = sorted
+            .iter()
+            .rposition(|packet| *packet == divider)
+            .unwrap();
+        assert_eq!(position + 1, 4);
+    }
+
+    #[test]
+    fn test_compare_packets_errors() {
+        assert_eq!(
+            compare_packets("[1,x]", "[1,2]"),
+            Err(ParsePacketError {
+                position: 3,
+                kind: ParsePacketErrorKind::UnexpectedChar('x')
+            })
+        );
+        // the order is known before the bad input is reached
+        assert_eq!(compare_packets("[1,x]", "[2]"), Ok(Ordering::Less));
+    }
+}
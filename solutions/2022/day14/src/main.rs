@@ -4,8 +4,11 @@ mod sand;
 use std::sync::{mpsc, Arc};
 
 use map::Map;
-use sand::FallingSand;
-use shared::{read_input, receive_answers, run_part_threaded, types_2d::Coords};
+use sand::{count_reachable, FallRules, SimulationEnd};
+use shared::{
+    read_input, receive_answers, run_part_threaded,
+    types_2d::{visualisation::Recorder, Coords},
+};
 
 type Answer = usize;
 
@@ -44,80 +47,175 @@ fn part1(input: &str) -> Answer {
     let mut map = parse_input(input, source);
 
     // simulate all the falling sand until a grain of sand falls out of bounds
-    let mut sand = FallingSand::new(source);
-    while let Ok(position) = sand.fall(&map) {
-        // the sand will fall until it rests somewhere in the map (or error if it goes out of bounds)
-        map.add_sand(position)
-            .expect("should not reach as high as source in part1");
-        sand = FallingSand::new(source);
-    }
-    println!("{:?}", map);
+    let mut recorder = Recorder::from_env("day14-part1", map::style);
+    let end = map.simulate_with(&FallRules::default(), |map| recorder.record(map));
+    assert_eq!(
+        end,
+        SimulationEnd::FellIntoAbyss,
+        "should not reach as high as source in part1"
+    );
+    recorder.finish(&map).expect("write visualisation");
     map.count_resting_sand()
 }
 
 fn part2(input: &str) -> Answer {
     let source = Coords::new(500, 0);
     let mut map = parse_input(input, source);
+    map.add_floor();
 
-    map.add_floor(source);
-
-    let mut sand = FallingSand::new(source);
-    while let Ok(position) = sand.fall(&map) {
-        match map.add_sand(position) {
-            Ok(_) => sand = FallingSand::new(source),
-            Err(_) => break,
-        }
-    }
-    println!("{:?}", map);
-    map.count_resting_sand() + 1 // for the unit of sand at the source
+    // with a floor in the way, the sand eventually fills everywhere it can reach
+    count_reachable(&map, source, &FallRules::default())
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use sand::SandPile;
+    use shared::types_2d::Vector;
 
     const INPUT: &str = r"498,4 -> 498,6 -> 496,6
 503,4 -> 502,4 -> 502,9 -> 494,9";
 
     #[test]
     fn test_falling_sand() {
+        let source = Coords::new(500, 0);
+        let rules = FallRules::default();
+        let mut map = parse_input(INPUT, source);
+        let mut pile = SandPile::new(source);
+
+        for (expected, count) in [(500, 8), (499, 8), (501, 8), (500, 7), (498, 8)]
+            .into_iter()
+            .zip(1..)
+        {
+            let position = pile.drop_grain(&map, &rules).expect("test");
+            map.add_sand(position);
+            assert_eq!(position, Coords::new(expected.0, expected.1));
+            assert_eq!(map.count_resting_sand(), count);
+        }
+    }
+
+    #[test]
+    fn test_floor() {
+        let source = Coords::new(500, 0);
+        let mut map = parse_input(INPUT, source);
+        let reachable = count_reachable(&map, source, &FallRules::default());
+
+        map.add_floor();
+        assert_eq!(
+            map.simulate_with(&FallRules::default(), |_| {}),
+            SimulationEnd::SourceBlocked
+        );
+        assert!(map.is_blocked(source));
+        assert_eq!(map.count_resting_sand(), reachable);
+    }
+
+    #[test]
+    fn test_fall_rules() {
+        // falling right before left in a mirror image of the cave piles up sand in the same way
+        let mirrored = INPUT
+            .lines()
+            .map(|line| {
+                line.split(" -> ")
+                    .map(|corner| {
+                        let (x, y) = corner.split_once(',').unwrap();
+                        format!("{},{y}", 1000 - x.parse::<usize>().unwrap())
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" -> ")
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let rules = FallRules::new(vec![
+            Vector::new(0, 1),
+            Vector::new(1, 1),
+            Vector::new(-1, 1),
+        ])
+        .unwrap();
+        let source = Coords::new(500, 0);
+
+        let mut map = parse_input(&mirrored, source);
+        assert_eq!(
+            map.simulate_with(&rules, |_| {}),
+            SimulationEnd::FellIntoAbyss
+        );
+        assert_eq!(map.count_resting_sand(), 24);
+
+        let mut map = parse_input(&mirrored, source);
+        map.add_floor();
+        assert_eq!(
+            map.simulate_with(&rules, |_| {}),
+            SimulationEnd::SourceBlocked
+        );
+        assert_eq!(map.count_resting_sand(), 93);
+    }
+
+    #[test]
+    fn test_count_reachable_with_rules() {
+        // sand that can slide two to the right, or drop two rows past a thin ledge
+        let rules = FallRules::new(vec![
+            Vector::new(0, 2),
+            Vector::new(2, 1),
+            Vector::new(-1, 1),
+        ])
+        .unwrap();
         let source = Coords::new(500, 0);
         let mut map = parse_input(INPUT, source);
-        let mut sand = FallingSand::new(source);
-        let position = sand.fall(&map).expect("test");
-
-        map.add_sand(position)
-            .expect("sand should not reach source");
-        assert_eq!(position, Coords::new(500, 8));
-        assert_eq!(map.count_resting_sand(), 1);
-
-        let mut sand = FallingSand::new(source);
-        let position = sand.fall(&map).expect("test");
-        map.add_sand(position)
-            .expect("sand should not reach source");
-        assert_eq!(position, Coords::new(499, 8));
-        assert_eq!(map.count_resting_sand(), 2);
-
-        let mut sand = FallingSand::new(source);
-        let position = sand.fall(&map).expect("test");
-        map.add_sand(position)
-            .expect("sand should not reach source");
-        assert_eq!(position, Coords::new(501, 8));
-        assert_eq!(map.count_resting_sand(), 3);
-
-        let mut sand = FallingSand::new(source);
-        let position = sand.fall(&map).expect("test");
-        map.add_sand(position)
-            .expect("sand should not reach source");
-        assert_eq!(position, Coords::new(500, 7));
-        assert_eq!(map.count_resting_sand(), 4);
-
-        let mut sand = FallingSand::new(source);
-        let position = sand.fall(&map).expect("test");
-        map.add_sand(position)
-            .expect("sand should not reach source");
-        assert_eq!(position, Coords::new(498, 8));
-        assert_eq!(map.count_resting_sand(), 5);
+        let reachable = count_reachable(&map, source, &rules);
+        assert_ne!(
+            reachable,
+            count_reachable(&map, source, &FallRules::default())
+        );
+
+        map.add_floor();
+        assert_eq!(
+            map.simulate_with(&rules, |_| {}),
+            SimulationEnd::SourceBlocked
+        );
+        assert_eq!(map.count_resting_sand(), reachable);
+    }
+
+    #[test]
+    fn test_fall_rules_must_go_down() {
+        assert!(FallRules::new(vec![Vector::new(0, 1), Vector::new(-1, 0)]).is_err());
+        assert!(FallRules::new(vec![Vector::new(1, -1)]).is_err());
+        assert!(FallRules::new(vec![Vector::new(0, 2), Vector::new(-3, 1)]).is_ok());
+    }
+
+    #[test]
+    fn test_multiple_sources() {
+        let rules = FallRules::default();
+        let sources = [
+            Coords::new(500, 0),
+            Coords::new(495, 1),
+            Coords::new(503, 2),
+        ];
+        let new_map = || {
+            let mut map = parse_input(INPUT, sources[0]);
+            sources[1..]
+                .iter()
+                .for_each(|&source| map.add_source(source));
+            map.add_floor();
+            map
+        };
+
+        let mut map = new_map();
+        assert_eq!(
+            map.simulate_with(&rules, |_| {}),
+            SimulationEnd::SourceBlocked
+        );
+
+        // drop every grain from the top without remembering any paths, taking turns in the same order
+        let mut naive = new_map();
+        while !sources.iter().all(|&source| naive.is_blocked(source)) {
+            for &source in &sources {
+                if let Ok(position) = SandPile::new(source).drop_grain(&naive, &rules) {
+                    naive.add_sand(position);
+                }
+            }
+        }
+
+        assert_eq!(map.count_resting_sand(), naive.count_resting_sand());
+        assert!(sources.iter().all(|&source| map.is_blocked(source)));
     }
 
     #[test]
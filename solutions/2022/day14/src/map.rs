@@ -1,13 +1,19 @@
 use std::fmt;
 
-use shared::types_2d::{Coords, InfGrid, Itertools, OutOfBounds, Size, Vector};
+use shared::types_2d::{
+    visualisation::{Drawable, Rgb, Style},
+    Bounds, Coords, InfGrid, Itertools, OutOfBounds, Size, Vector,
+};
 
-use crate::sand::Entity;
+use crate::sand::{Entity, FallRules, SandPile, SimulationEnd};
 use Entity::*;
 
 pub struct Map {
     pub bounds: Size,
     grid: InfGrid<Entity>,
+    sources: Vec<Coords>,
+    /// every position at this height is solid rock, however far left or right it is
+    floor: Option<usize>,
 }
 
 impl Map {
@@ -15,39 +21,78 @@ impl Map {
         let mut map = Map {
             bounds: Size::zero(),
             grid: InfGrid::new_off_center(source),
+            sources: Vec::new(),
+            floor: None,
         };
         for rock_seam in rock_seams {
             map.add_rock_seam(rock_seam);
         }
         map.add_source(source);
-
-        map.bounds = map.grid.bounds_size();
         map
     }
 
+    /// what is at `coords`, or `OutOfBounds` if it is past the rock and there is no floor to stop sand falling forever
     pub fn get(&self, coords: Coords) -> Result<Option<Entity>, OutOfBounds> {
-        if coords.outside_of(self.bounds, Coords::try_from(self.grid.offset())?) {
-            Err(OutOfBounds(Vector::from(coords)))
-        } else {
-            Ok(self
-                .grid
-                .get(Vector::from(coords))
-                .and_then(|cell| cell.value))
+        match self.floor {
+            Some(floor) if coords.y >= floor => Ok(Some(Rock)),
+            None if coords.outside_of(self.bounds, Coords::try_from(self.grid.offset())?) => {
+                Err(OutOfBounds(Vector::from(coords)))
+            }
+            _ => Ok(self.grid.get(Vector::from(coords)).copied()),
         }
     }
 
+    /// add another source of sand, they take it in turns to drop a grain
     pub fn add_source(&mut self, position: Coords) {
-        self.grid.add(Vector::from(position), Source, false);
+        self.grid.add(Vector::from(position), Source);
+        self.sources.push(position);
+        self.bounds = self.grid.bounds_size();
+    }
+
+    pub fn add_sand(&mut self, position: Coords) {
+        self.grid.add(Vector::from(position), Sand);
+    }
+
+    /// whether sand has piled up so high that it covers `source`
+    pub fn is_blocked(&self, source: Coords) -> bool {
+        matches!(self.get(source), Ok(Some(Sand)))
     }
 
-    pub fn add_sand(&mut self, position: Coords) -> Result<(), OutOfBounds> {
-        if let Ok(Some(Source)) = self.get(position) {
-            // declare "out of bounds" if we reach the source of the sound to terminate the sand adding loop in part 2
-            Err(OutOfBounds(Vector::from(position)))
-        } else {
-            self.grid.add(Vector::from(position), Sand, true);
-            Ok(())
+    /// drop sand from every source in turn until one grain falls into the abyss, or every source is blocked,
+    /// calling `on_rest` with the map each time a grain of sand comes to rest
+    pub fn simulate_with<F>(&mut self, rules: &FallRules, mut on_rest: F) -> SimulationEnd
+    where
+        F: FnMut(&Map),
+    {
+        let mut piles: Vec<_> = self
+            .sources
+            .iter()
+            .map(|&source| SandPile::new(source))
+            .collect();
+        let mut blocked = vec![false; piles.len()];
+
+        while blocked.contains(&false) {
+            for i in 0..piles.len() {
+                if blocked[i] {
+                    continue;
+                }
+                match piles[i].drop_grain(self, rules) {
+                    Ok(position) => {
+                        self.add_sand(position);
+                        on_rest(self);
+                        // the other piles can no longer fall through where this grain came to rest
+                        for (j, pile) in piles.iter_mut().enumerate() {
+                            if j != i {
+                                pile.block(position);
+                            }
+                        }
+                    }
+                    Err(SimulationEnd::SourceBlocked) => blocked[i] = true,
+                    Err(end) => return end,
+                }
+            }
         }
+        SimulationEnd::SourceBlocked
     }
 
     pub fn add_rock_seam(&mut self, corners: Vec<Coords>) {
@@ -59,22 +104,25 @@ impl Map {
 
     pub fn add_rock_line_segment(&mut self, start: &Coords, end: &Coords) {
         for point in start.points_between_inclusive(end) {
-            self.grid.add(Vector::from(point), Rock, false)
+            self.grid.add(Vector::from(point), Rock)
         }
     }
 
-    /// add the "infinite" floor by placing a rock seam 2 below the height of the map, stretching across what is hopefully far enough
-    pub fn add_floor(&mut self, source: Coords) {
-        let new_height = self.bounds.height + 1;
-        let start = Coords::new(source.x - new_height, new_height);
-        let end = Coords::new(source.x + new_height, new_height);
+    /// add the infinite floor 2 below the lowest rock
+    pub fn add_floor(&mut self) {
+        self.floor = Some(self.floor_y());
+    }
 
-        self.add_rock_line_segment(&start, &end);
-        self.bounds = self.grid.bounds_size();
+    /// the y coordinate of the floor, whether or not it has been added yet
+    pub fn floor_y(&self) -> usize {
+        self.floor.unwrap_or(self.bounds.height + 1)
     }
 
     pub fn count_resting_sand(&self) -> usize {
-        self.grid.count_visited()
+        self.grid
+            .values()
+            .filter(|&&entity| matches!(entity, Sand))
+            .count()
     }
 }
 
@@ -84,6 +132,38 @@ impl fmt::Debug for Map {
     }
 }
 
+/// the floor isn't stored in the grid, so this is drawn in its place
+const FLOOR: Entity = Rock;
+
+impl Drawable for Map {
+    type Cell = Entity;
+
+    fn draw_bounds(&self) -> Bounds {
+        let mut bounds = self.grid.bounds();
+        if let Some(floor) = self.floor {
+            bounds.bottom_right.y = floor as isize;
+        }
+        bounds
+    }
+
+    fn draw_cell(&self, position: Vector) -> Option<&Self::Cell> {
+        match self.floor {
+            Some(floor) if position.y >= floor as isize => Some(&FLOOR),
+            _ => self.grid.get(position),
+        }
+    }
+}
+
+/// how to draw each cell of the map when visualising the simulation
+pub fn style(cell: Option<&Entity>, _marked: bool) -> Style {
+    match cell.copied() {
+        Some(Rock) => Style::coloured('#', Rgb(128, 128, 128)),
+        Some(Sand) => Style::coloured('o', Rgb(230, 200, 110)),
+        Some(Source) => Style::coloured('+', Rgb(220, 50, 50)),
+        Some(Air) | None => Style::plain(' '),
+    }
+}
+
 impl fmt::Debug for Entity {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -1,5 +1,8 @@
+use std::collections::{BTreeMap, BTreeSet};
+
 use shared::types_2d::OutOfBounds;
 use shared::types_2d::{Coords, Vector};
+use shared::ValueError;
 
 use crate::map::Map;
 
@@ -14,35 +17,128 @@ pub enum Entity {
 
 use Entity::*;
 
-pub struct FallingSand {
-    position: Coords,
+/// How grains of sand move while they are falling
+#[derive(Clone, Debug)]
+pub struct FallRules {
+    /// the moves a grain of sand tries in order of preference, it comes to rest when none of them are free
+    moves: Vec<Vector>,
+}
+
+impl Default for FallRules {
+    /// straight down, then down and to the left, then down and to the right
+    fn default() -> Self {
+        FallRules::new(vec![
+            Vector::new(0, 1),
+            Vector::new(-1, 1),
+            Vector::new(1, 1),
+        ])
+        .expect("every move goes down")
+    }
+}
+
+impl FallRules {
+    /// sand tries each of `moves` in order, every one of which has to go down so that sand can't fall forever
+    pub fn new(moves: Vec<Vector>) -> Result<Self, ValueError<String>> {
+        match moves.iter().find(|direction| direction.y <= 0) {
+            Some(direction) => Err(ValueError(format!("sand can't fall by {direction:?}"))),
+            None => Ok(FallRules { moves }),
+        }
+    }
+
+    /// where sand falls next from `position`, or `None` if it comes to rest there
+    fn next_position(&self, position: Coords, map: &Map) -> Result<Option<Coords>, OutOfBounds> {
+        for &direction in &self.moves {
+            let next = (position + direction)?;
+            match map.get(next)? {
+                Some(Rock) | Some(Sand) => continue,
+                _ => return Ok(Some(next)),
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Why sand stopped piling up
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SimulationEnd {
+    /// a grain of sand fell past all of the rock
+    FellIntoAbyss,
+    /// sand has piled up all the way to the source, so no more can be dropped
+    SourceBlocked,
+}
+
+/// Drops grains of sand one after another, remembering the path the last grain took
+///
+/// Each grain follows exactly the same path as the previous one until it reaches where that one came to rest,
+/// so rather than falling all the way from the source, the next grain resumes from the end of the previous path.
+pub struct SandPile {
+    source: Coords,
+    path: Vec<Coords>,
 }
 
-impl FallingSand {
+impl SandPile {
     pub fn new(source: Coords) -> Self {
-        FallingSand { position: source }
+        SandPile {
+            source,
+            path: vec![source],
+        }
+    }
+
+    /// the position the next grain of sand comes to rest, which must then be added to the map
+    pub fn drop_grain(&mut self, map: &Map, rules: &FallRules) -> Result<Coords, SimulationEnd> {
+        let mut position = match self.path.last() {
+            Some(&position) => position,
+            None if map.is_blocked(self.source) => return Err(SimulationEnd::SourceBlocked),
+            None => {
+                self.path.push(self.source);
+                self.source
+            }
+        };
+
+        while let Some(next) = rules
+            .next_position(position, map)
+            .map_err(|_| SimulationEnd::FellIntoAbyss)?
+        {
+            self.path.push(next);
+            position = next;
+        }
+
+        // this grain will fill its resting place, so the next one can only get as far as the position before it
+        self.path.pop();
+        Ok(position)
     }
-    pub fn fall(&mut self, map: &Map) -> Result<Coords, OutOfBounds> {
-        if let Some(next) = self.test_fall(Vector::new(0, 1), map)? {
-            self.position = next;
-            self.fall(map)
-        } else if let Some(next) = self.test_fall(Vector::new(-1, 1), map)? {
-            self.position = next;
-            self.fall(map)
-        } else if let Some(next) = self.test_fall(Vector::new(1, 1), map)? {
-            self.position = next;
-            self.fall(map)
-        } else {
-            // this is the position of the sand at rest
-            Ok(self.position)
+
+    /// forget the part of the path from `position` onwards, because sand from somewhere else has come to rest there
+    pub fn block(&mut self, position: Coords) {
+        if let Some(index) = self.path.iter().position(|&step| step == position) {
+            self.path.truncate(index);
         }
     }
+}
+
+/// The number of positions sand can reach when falling from the source, including the source itself
+///
+/// Once sand has stopped falling it fills every one of these positions, so with a floor this is the answer to part 2
+/// without needing to simulate each grain. A position is reachable if it isn't rock, is above the floor, and one of
+/// the `rules`' moves takes sand there from a position that is reachable. Every move goes down, so each row only
+/// depends on the rows above it.
+pub fn count_reachable(map: &Map, source: Coords, rules: &FallRules) -> usize {
+    let floor_y = map.floor_y();
+    let mut rows = BTreeMap::from([(source.y, BTreeSet::from([source.x]))]);
+    let mut count = 0;
 
-    pub fn test_fall(&self, to: Vector, map: &Map) -> Result<Option<Coords>, OutOfBounds> {
-        let position = (self.position + to)?;
-        match map.get(position)? {
-            Some(Rock) | Some(Sand) => Ok(None),
-            _ => Ok(Some(position)),
+    while let Some((y, row)) = rows.pop_first() {
+        count += row.len();
+        for x in row {
+            for &direction in &rules.moves {
+                let Ok(next) = Coords::new(x, y) + direction else {
+                    continue;
+                };
+                if next.y < floor_y && !matches!(map.get(next), Ok(Some(Rock))) {
+                    rows.entry(next.y).or_default().insert(next.x);
+                }
+            }
         }
     }
+    count
 }
@@ -1,12 +1,16 @@
 mod sensor;
 
-use std::sync::{mpsc, Arc};
+use std::{
+    collections::BTreeSet,
+    sync::{mpsc, Arc},
+};
 
 use regex::Regex;
-use sensor::{Coverage, Map, Sensor, TotalCoverage};
+use sensor::{Map, Sensor};
 use shared::{
-    read_input, receive_answers, run_part_threaded,
-    types_2d::{iter_vectors, Vector},
+    intervals::IntervalSet,
+    parallel, read_input, receive_answers, run_part_threaded,
+    types_2d::{iter_vectors, Itertools, Vector},
 };
 
 use crate::sensor::Entity;
@@ -15,33 +19,8 @@ type Answer = usize;
 
 fn parse_input(input: &str) -> Map {
     let mut map = Map::new();
-    let sensors = parse_sensors(input);
-    let mut top_left = Vector::zero();
-    let mut bottom_right = Vector::zero();
-    for sensor in sensors {
-        let reach = sensor.distance_to_beacon() as isize;
-        let left = sensor.position.x - reach;
-        let right = sensor.position.x + reach;
-        let top = sensor.position.y - reach;
-        let bottom = sensor.position.y + reach;
-
-        if left < top_left.x {
-            top_left.x = left;
-        }
-        if right + reach > bottom_right.x {
-            bottom_right.x = right;
-        }
-        if top < top_left.y {
-            top_left.y = top;
-        }
-        if bottom < bottom_right.y {
-            bottom_right.y = bottom;
-        }
-
-        map.add(sensor.closest_beacon, Entity::Beacon);
-        map.add(sensor.position, Entity::Sensor(sensor));
-        map.grid.top_left = top_left;
-        map.grid.bottom_right = bottom_right;
+    for sensor in parse_sensors(input) {
+        map.add_sensor(&sensor);
     }
     map
 }
@@ -86,14 +65,24 @@ fn part1_inner(input: &str, y: isize) -> Answer {
     let map = parse_input(input);
     let sensors: Vec<_> = map.sensors().collect();
 
-    let mut total_coverage = TotalCoverage::new();
-    for sensor in sensors.iter() {
-        if let Some(sensor_coverage) = sensor.coverage_at_y(y, isize::MIN, isize::MAX) {
-            total_coverage.add_coverage(sensor_coverage);
-        }
+    count_no_beacon(&sensors, y)
+}
+
+/// the number of positions in row `y` where a beacon cannot be
+///
+/// That is every position closer to a sensor than its closest beacon (so within its range),
+/// apart from the positions of the beacons themselves.
+fn count_no_beacon(sensors: &[Sensor], y: isize) -> usize {
+    let mut total_coverage: IntervalSet<isize> = sensors
+        .iter()
+        .filter_map(|sensor| sensor.coverage_at_y(y))
+        .collect();
+    for sensor in sensors.iter().filter(|sensor| sensor.closest_beacon.y == y) {
+        let x = sensor.closest_beacon.x;
+        total_coverage.remove(x..x + 1);
     }
 
-    total_coverage.count()
+    total_coverage.len() as usize
 }
 
 #[allow(unused)]
@@ -126,25 +115,80 @@ fn part2(input: &str) -> Answer {
 fn part2_inner(input: &str, max: isize) -> Answer {
     let map = parse_input(input);
     let sensors: Vec<_> = map.sensors().collect();
-    let mut y = 0;
-    let mut gap = None;
 
-    while y <= max {
-        let mut total_coverage = TotalCoverage::new();
-        for sensor in sensors.iter() {
-            if let Some(sensor_coverage) = sensor.coverage_at_y(y, 0, max) {
-                total_coverage.add_coverage(sensor_coverage);
-            }
-        }
-        if total_coverage.coverage != Some(Coverage::new(0, max)) {
-            gap = total_coverage.gap(0, max);
-            break;
-        }
-        y += 1;
+    let beacon = find_beacon(&sensors, max)
+        .or_else(|| scan_for_beacon(&sensors, max))
+        .expect("unique valid solution");
+    (beacon.x * 4_000_000 + beacon.y) as usize
+}
+
+/// find the only position within 0..=max in both directions that isn't covered by any sensor
+///
+/// If there is only one such position then it must be hemmed in by the edges of sensor ranges (or the search area).
+/// Those edges are diagonal lines, which are straight lines in rotated space (u = x + y, v = x - y),
+/// so the beacon is usually where one of those lines crosses another line or the edge of the search area.
+/// It can also sit in a one wide diagonal gap between two parallel lines 2 apart, when the lines closing off the ends
+/// of the gap have the wrong parity to cross it, so the line down the middle of each such gap is tried as well.
+/// That leaves O(n²) candidates to check instead of every position, but this is a shortcut rather than a proof,
+/// so `None` means the caller should fall back to checking every row.
+fn find_beacon(sensors: &[Sensor], max: isize) -> Option<Vector> {
+    let mut us = BTreeSet::new();
+    let mut vs = BTreeSet::new();
+    for sensor in sensors {
+        let (u, v) = sensor.boundary_lines();
+        us.extend(u);
+        vs.extend(v);
     }
+    let middles = |lines: &BTreeSet<isize>| -> Vec<isize> {
+        lines
+            .iter()
+            .filter(|&&line| lines.contains(&(line + 2)))
+            .map(|line| line + 1)
+            .collect()
+    };
+    let (u_middles, v_middles) = (middles(&us), middles(&vs));
+    us.extend(u_middles);
+    vs.extend(v_middles);
+
+    // a u line meets a v line where x = (u + v) / 2, which needs u and v to have the same parity
+    let crossings = us
+        .iter()
+        .cartesian_product(vs.iter())
+        .filter(|(u, v)| (*u + *v) % 2 == 0)
+        .map(|(u, v)| Vector::new((u + v) / 2, (u - v) / 2));
+    let along_edges = [0, max].into_iter().flat_map(|edge| {
+        let us = us
+            .iter()
+            .flat_map(move |u| [Vector::new(edge, u - edge), Vector::new(u - edge, edge)]);
+        let vs = vs
+            .iter()
+            .flat_map(move |v| [Vector::new(edge, edge - v), Vector::new(v + edge, edge)]);
+        us.chain(vs)
+    });
+    let corners = [0, max]
+        .into_iter()
+        .cartesian_product([0, max])
+        .map(|(x, y)| Vector::new(x, y));
+
+    crossings
+        .chain(along_edges)
+        .chain(corners)
+        .filter(|point| (0..=max).contains(&point.x) && (0..=max).contains(&point.y))
+        .find(|&point| !sensors.iter().any(|sensor| sensor.covers(point)))
+}
 
-    let x = gap.expect("unique valid solution");
-    x * 4_000_000 + y as usize
+/// scan every row for a gap in the sensors' coverage, spread across all the available threads
+///
+/// Much slower than [`find_beacon`], but it doesn't depend on the beacon being boxed in by sensor boundaries.
+fn scan_for_beacon(sensors: &[Sensor], max: isize) -> Option<Vector> {
+    parallel::find_map(0..max + 1, 10_000, |y| {
+        let total_coverage: IntervalSet<isize> = sensors
+            .iter()
+            .filter_map(|sensor| sensor.coverage_at_y(y))
+            .collect();
+        let gap = total_coverage.gaps(0..max + 1).next()?;
+        Some(Vector::new(gap.start, y))
+    })
 }
 
 // too slow for real inputs
@@ -171,8 +215,6 @@ fn part2_inner_bruteforce(input: &str, max: isize) -> Answer {
 
 #[cfg(test)]
 mod test {
-    use crate::sensor::{Coverage, TotalCoverage};
-
     use super::*;
 
     const INPUT: &str = r"Sensor at x=2, y=18: closest beacon is at x=-2, y=15
@@ -195,20 +237,71 @@ Sensor at x=20, y=1: closest beacon is at x=15, y=3";
         assert_eq!(part1_inner(INPUT, 10), 26);
     }
 
+    #[test]
+    fn test_count_single_span() {
+        let sensors = parse_sensors("Sensor at x=0, y=0: closest beacon is at x=2, y=1");
+        assert_eq!(count_no_beacon(&sensors, 0), 7);
+        assert_eq!(count_no_beacon(&sensors, 1), 4);
+        assert_eq!(count_no_beacon(&sensors, -2), 3);
+        assert_eq!(count_no_beacon(&sensors, 3), 1);
+        assert_eq!(count_no_beacon(&sensors, 4), 0);
+    }
+
+    #[test]
+    fn test_count_multiple_spans() {
+        let sensors = parse_sensors(
+            "Sensor at x=0, y=0: closest beacon is at x=0, y=-3
+Sensor at x=10, y=0: closest beacon is at x=10, y=2
+Sensor at x=6, y=0: closest beacon is at x=6, y=1",
+        );
+        // -3..=3, 5..=7 and 8..=12, where the last two touch
+        assert_eq!(count_no_beacon(&sensors, 0), 7 + 3 + 5);
+    }
+
+    #[test]
+    fn test_count_beacon_on_row() {
+        let sensors = parse_sensors("Sensor at x=0, y=0: closest beacon is at x=3, y=0");
+        assert_eq!(count_no_beacon(&sensors, 0), 6);
+
+        // both sensors' closest beacon is the same one, which should only be taken away once
+        let sensors = parse_sensors(
+            "Sensor at x=0, y=0: closest beacon is at x=3, y=0
+Sensor at x=6, y=0: closest beacon is at x=3, y=0",
+        );
+        assert_eq!(count_no_beacon(&sensors, 0), 12);
+    }
+
+    #[test]
+    fn test_count_matches_naive() {
+        for y in -5..30 {
+            assert_eq!(
+                part1_inner(INPUT, y),
+                part1_inner_naive(INPUT, y),
+                "y = {y}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_bounds() {
+        let map = parse_input(INPUT);
+        assert_eq!(map.bounds().top_left, Vector::new(-8, -10));
+        assert_eq!(map.bounds().bottom_right, Vector::new(28, 26));
+    }
+
     #[test]
     fn test_sensor_distance() {
         let mut map = Map::new();
         let sensors = parse_sensors(INPUT);
         for sensor in sensors {
-            map.add(sensor.closest_beacon, Entity::Beacon);
-            map.add(sensor.position, Entity::Sensor(sensor));
+            map.add_sensor(&sensor);
         }
 
-        if let Entity::Sensor(test_sensor) = map.get(8, 7).unwrap() {
-            assert_eq!(test_sensor.distance_to_beacon(), 9);
-        } else {
-            panic!("sensor exists")
-        }
+        let test_sensor = map
+            .sensors()
+            .find(|sensor| sensor.position == Vector::new(8, 7))
+            .expect("sensor exists");
+        assert_eq!(test_sensor.distance_to_beacon(), 9);
     }
 
     #[test]
@@ -216,32 +309,25 @@ Sensor at x=20, y=1: closest beacon is at x=15, y=3";
         let mut map = Map::new();
         let sensors = parse_sensors(INPUT);
         for sensor in sensors.iter() {
-            map.add(sensor.closest_beacon, Entity::Beacon);
-            map.add(sensor.position, Entity::Sensor(sensor.clone()));
+            map.add_sensor(sensor);
         }
 
         let test_row = 10;
         let min = 0;
         let max = 20;
 
-        let total_coverage = TotalCoverage::new();
-
-        if let Entity::Sensor(test_sensor) = map.get(8, 7).unwrap() {
-            let test_coverage = test_sensor.coverage_at_y(test_row, min, max);
-            assert_eq!(test_coverage.as_ref(), Some(&Coverage::new(2, 14)));
-        } else {
-            panic!("test sensor");
-        }
+        let test_sensor = map
+            .sensors()
+            .find(|sensor| sensor.position == Vector::new(8, 7))
+            .expect("test sensor");
+        assert_eq!(test_sensor.coverage_at_y(test_row), Some(2..15));
 
-        let total_coverage = sensors
+        let total_coverage: IntervalSet<isize> = sensors
             .iter()
-            .filter_map(|sensor| sensor.coverage_at_y(test_row, min, max))
-            .fold(total_coverage, |mut total_coverage, sensor_coverage| {
-                total_coverage.add_coverage(sensor_coverage);
-                dbg!(total_coverage)
-            });
+            .filter_map(|sensor| sensor.coverage_at_y(test_row))
+            .collect();
 
-        assert_eq!(total_coverage.coverage, Some(Coverage::new(0, max)));
+        assert_eq!(total_coverage.gaps(min..max + 1).next(), None);
     }
 
     #[test]
@@ -250,8 +336,7 @@ Sensor at x=20, y=1: closest beacon is at x=15, y=3";
         let sensors = parse_sensors(INPUT);
         let sensor_count = sensors.len();
         for sensor in sensors {
-            map.add(sensor.closest_beacon, Entity::Beacon);
-            map.add(sensor.position, Entity::Sensor(sensor));
+            map.add_sensor(&sensor);
         }
 
         assert_eq!(map.sensors().count(), sensor_count);
@@ -266,4 +351,52 @@ Sensor at x=20, y=1: closest beacon is at x=15, y=3";
     fn test_part2_bruteforce() {
         assert_eq!(part2_inner_bruteforce(INPUT, 20), 56000011);
     }
+
+    #[test]
+    fn test_part2_scan() {
+        let sensors = parse_sensors(INPUT);
+        assert_eq!(scan_for_beacon(&sensors, 20), Some(Vector::new(14, 11)));
+        assert_eq!(scan_for_beacon(&sensors, 10), None);
+    }
+
+    #[test]
+    fn test_beacon_in_corner() {
+        // covers everything in 0..=10 apart from the top left corner, which isn't on any crossing of boundary lines
+        let input = "Sensor at x=10, y=10: closest beacon is at x=0, y=1";
+        assert_eq!(part2_inner(input, 10), part2_inner_bruteforce(input, 10));
+        assert_eq!(
+            scan_for_beacon(&parse_sensors(input), 10),
+            Some(Vector::zero())
+        );
+        assert_eq!(part2_inner(input, 10), 0);
+    }
+
+    #[test]
+    fn test_beacon_on_edge() {
+        // (0, 7) is hemmed in by the edge of the search area, it isn't on any crossing of boundary lines
+        let input = "Sensor at x=6, y=1: closest beacon is at x=6, y=-1
+Sensor at x=-3, y=3: closest beacon is at x=-3, y=-3
+Sensor at x=6, y=2: closest beacon is at x=6, y=-7
+Sensor at x=2, y=8: closest beacon is at x=2, y=6";
+        assert_eq!(part2_inner(input, 8), part2_inner_bruteforce(input, 8));
+        assert_eq!(
+            scan_for_beacon(&parse_sensors(input), 8),
+            Some(Vector::new(0, 7))
+        );
+        assert_eq!(part2_inner(input, 8), 7);
+    }
+
+    #[test]
+    fn test_beacon_between_parallel_lines() {
+        // (10, 10) is in a one wide diagonal gap, and the lines closing off its ends can't cross it
+        let input = "Sensor at x=30, y=30: closest beacon is at x=30, y=69
+Sensor at x=-10, y=-10: closest beacon is at x=-10, y=29
+Sensor at x=20, y=0: closest beacon is at x=20, y=18
+Sensor at x=0, y=20: closest beacon is at x=0, y=2";
+        let sensors = parse_sensors(input);
+        assert_eq!(find_beacon(&sensors, 20), Some(Vector::new(10, 10)));
+        assert_eq!(part2_inner_bruteforce(input, 20), 40000010);
+        assert_eq!(scan_for_beacon(&sensors, 20), Some(Vector::new(10, 10)));
+        assert_eq!(part2_inner(input, 20), 40000010);
+    }
 }